@@ -1,25 +1,41 @@
 mod mcp;
 
+use crate::mcp::config::{
+    Config, DEFAULT_ESPLORA_URL, DEFAULT_FAUCET_URL, DEFAULT_GITHUB_URL, get_config_location,
+};
+use crate::mcp::esplora::EsploraClient;
+use crate::mcp::faucet::FaucetClient;
+use crate::mcp::http::HttpOptions;
+use crate::mcp::lnurl::LnurlClient;
+use crate::mcp::policy::Policy;
 use crate::mcp::prompts::{prompts_get, prompts_list};
 use crate::mcp::resources::{resource_read, resources_list};
 use crate::mcp::tools::{register_tools, tools_list};
-use crate::mcp::types::{
-    CancelledNotification, JsonRpcError, JsonRpcResponse, ToolCallRequestParams,
-};
+use crate::mcp::transcript::Transcript;
 use crate::mcp::utilities::*;
+use crate::mcp::{http, stdio};
 use clap::Parser;
-use rpc_router::{Error, Handler, Request, Router, RouterBuilder};
-use serde_json::{Value, json};
+use rpc_router::{Handler, Router, RouterBuilder};
 use signal_hook::consts::SIGTERM;
 use signal_hook::{consts::SIGINT, iterator::Signals};
-use std::fs::OpenOptions;
-use std::io;
-use std::io::Write;
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::thread;
+use std::time::Duration;
+use url::Url;
 
-fn build_rpc_router() -> Router {
+fn build_rpc_router(
+    faucet: FaucetClient,
+    policy: Policy,
+    esplora: EsploraClient,
+    lnurl: LnurlClient,
+) -> Router {
     let builder = RouterBuilder::default()
         // append resources here
+        .append_resource(faucet)
+        .append_resource(policy)
+        .append_resource(esplora)
+        .append_resource(lnurl)
         .append_dyn("initialize", initialize.into_dyn())
         .append_dyn("ping", ping.into_dyn())
         .append_dyn("logging/setLevel", logging_set_level.into_dyn())
@@ -36,94 +52,80 @@ fn build_rpc_router() -> Router {
 async fn main() {
     // clap args parser
     let args = Args::parse();
-    if !args.mcp {
+    if !args.mcp && args.http.is_none() {
         display_info(&args).await;
         return;
     }
     // signal handling to exit cli
     let mut signals = Signals::new([SIGTERM, SIGINT]).unwrap();
     thread::spawn(move || {
-        for _sig in signals.forever() {
+        if signals.forever().next().is_some() {
             graceful_shutdown();
             std::process::exit(0);
         }
     });
-    // process json-rpc from MCP client
-    let router = build_rpc_router();
-    let mut line = String::new();
-    let input = io::stdin();
-    let mut logging_file = OpenOptions::new()
-        .write(true)
-        .append(true)
-        .create(true)
-        .open("/tmp/mcp.jsonl")
-        .unwrap();
-    while input.read_line(&mut line).unwrap() != 0 {
-        let line = std::mem::take(&mut line);
-        writeln!(logging_file, "{}", line).unwrap();
-        if !line.is_empty() {
-            if let Ok(json_value) = serde_json::from_str::<Value>(&line) {
-                // notifications, no response required
-                if json_value.is_object() && json_value.get("id").is_none() {
-                    if let Some(method) = json_value.get("method") {
-                        if method == "notifications/initialized" {
-                            notifications_initialized();
-                        } else if method == "notifications/cancelled" {
-                            let params_value = json_value.get("params").unwrap();
-                            let cancel_params: CancelledNotification =
-                                serde_json::from_value(params_value.clone()).unwrap();
-                            notifications_cancelled(cancel_params);
-                        }
-                    }
-                } else if let Ok(mut rpc_request) = Request::from_value(json_value) {
-                    // normal json-rpc message, and response expected
-                    let id = rpc_request.id.clone();
-                    if rpc_request.method == "tools/call" {
-                        let params = serde_json::from_value::<ToolCallRequestParams>(
-                            rpc_request.params.unwrap(),
-                        )
-                        .unwrap();
-                        rpc_request = Request {
-                            id: id.clone(),
-                            method: params.name,
-                            params: params.arguments,
-                        }
-                    }
-                    match router.call(rpc_request).await {
-                        Ok(call_response) => {
-                            if !call_response.value.is_null() {
-                                let response =
-                                    JsonRpcResponse::new(id, call_response.value.clone());
-                                let response_json = serde_json::to_string(&response).unwrap();
-                                writeln!(logging_file, "{}\n", response_json).unwrap();
-                                println!("{}", response_json);
-                            }
-                        }
-                        Err(error) => match &error.error {
-                            // error from json-rpc call
-                            Error::Handler(handler) => {
-                                if let Some(error_value) = handler.get::<Value>() {
-                                    let json_error = json!({
-                                        "jsonrpc": "2.0",
-                                        "error": error_value,
-                                        "id": id
-                                    });
-                                    let response = serde_json::to_string(&json_error).unwrap();
-                                    writeln!(logging_file, "{}\n", response).unwrap();
-                                    println!("{}", response);
-                                }
-                            }
-                            _ => {
-                                let json_error = JsonRpcError::new(id, -1, "Invalid json-rpc call");
-                                let response = serde_json::to_string(&json_error).unwrap();
-                                writeln!(logging_file, "{}\n", response).unwrap();
-                                println!("{}", response);
-                            }
-                        },
-                    }
-                }
+    let config_path = args.config.clone().unwrap_or_else(get_config_location);
+    let config = match Config::load(&config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+    let faucet_url = args
+        .faucet_url
+        .clone()
+        .or(config.faucet_url)
+        .unwrap_or_else(|| Url::parse(DEFAULT_FAUCET_URL).unwrap());
+    let github_url = args
+        .github_url
+        .clone()
+        .or(config.github_url)
+        .unwrap_or_else(|| Url::parse(DEFAULT_GITHUB_URL).unwrap());
+    let esplora_url = args
+        .esplora_url
+        .clone()
+        .or(config.esplora_url)
+        .unwrap_or_else(|| Url::parse(DEFAULT_ESPLORA_URL).unwrap());
+    let router = build_rpc_router(
+        FaucetClient::new(faucet_url, github_url),
+        Policy::new(
+            config.limits,
+            args.confirm_payments || config.confirm_payments,
+            Duration::from_secs(args.confirm_timeout),
+            args.dry_run,
+        ),
+        EsploraClient::new(esplora_url),
+        LnurlClient::default(),
+    );
+    let transcript = args.transcript.as_ref().and_then(|path| {
+        match Transcript::open(path, args.transcript_max_bytes, args.transcript_redact) {
+            Ok(transcript) => Some(transcript),
+            Err(e) => {
+                eprintln!(
+                    "Failed to open transcript {}, continuing without it: {e}",
+                    path.display()
+                );
+                None
             }
         }
+    });
+    if let Some(addr) = args.http {
+        // serve json-rpc over streamable HTTP
+        let options = HttpOptions {
+            allowed_origins: args.http_allowed_origin.clone(),
+            session_timeout: Duration::from_secs(args.http_session_timeout),
+        };
+        if let Err(e) = http::serve(addr, router, transcript, options).await {
+            eprintln!("Failed to serve MCP over HTTP on {addr}: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+    // process json-rpc from MCP client
+    if let Err(e) = stdio::serve(router, transcript).await {
+        eprintln!("Failed to serve MCP over stdio: {e}");
+        std::process::exit(1);
     }
 }
 
@@ -142,6 +144,46 @@ struct Args {
     /// start MCP server
     #[arg(long, default_value = "false")]
     mcp: bool,
+    /// start MCP server over streamable HTTP on the given address instead of stdio
+    #[arg(long, value_name = "ADDR")]
+    http: Option<SocketAddr>,
+    /// origin allowed to reach the HTTP server from a browser besides localhost, can be repeated
+    #[arg(long, value_name = "ORIGIN")]
+    http_allowed_origin: Vec<String>,
+    /// drop HTTP sessions that have been idle for this many seconds
+    #[arg(long, value_name = "SECS", default_value_t = 30 * 60)]
+    http_session_timeout: u64,
+    /// path to the config file, defaults to ~/.config/mcp/mutinynet/config.toml
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// base url of the mutinynet faucet
+    #[arg(long, value_name = "URL", env = "MUTINYNET_FAUCET_URL")]
+    faucet_url: Option<Url>,
+    /// base url of github, used to log in to the faucet
+    #[arg(long, value_name = "URL", env = "MUTINYNET_GITHUB_URL")]
+    github_url: Option<Url>,
+    /// base url of the esplora api used to look up transactions
+    #[arg(long, value_name = "URL", env = "MUTINYNET_ESPLORA_URL")]
+    esplora_url: Option<Url>,
+    /// append every json-rpc message to this file, disabled by default
+    #[arg(long, value_name = "PATH")]
+    transcript: Option<PathBuf>,
+    /// rotate the transcript to `<PATH>.1` once it grows past this many bytes
+    #[arg(long, value_name = "BYTES", default_value_t = 10 * 1024 * 1024)]
+    transcript_max_bytes: u64,
+    /// redact bearer tokens, payment destinations and addresses from the transcript
+    #[arg(long, default_value = "false")]
+    transcript_redact: bool,
+    /// ask for approval through MCP elicitation before every payout,
+    /// payouts are refused if the client doesn't support it
+    #[arg(long, default_value = "false")]
+    confirm_payments: bool,
+    /// refuse a payout if the user hasn't answered its confirmation within this many seconds
+    #[arg(long, value_name = "SECS", default_value_t = 5 * 60)]
+    confirm_timeout: u64,
+    /// validate payouts and report what would be sent, without asking the faucet to pay
+    #[arg(long, default_value = "false")]
+    dry_run: bool,
     /// output as json-rpc format
     #[arg(long, default_value = "false")]
     json: bool,
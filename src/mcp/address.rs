@@ -0,0 +1,107 @@
+use bitcoin::address::NetworkUnchecked;
+use bitcoin::{Address, AddressType, Network};
+use std::str::FromStr;
+
+/// A bitcoin address that parsed and is valid on mutinynet
+pub struct ValidatedAddress {
+    pub address: String,
+    pub address_type: &'static str,
+}
+
+/// parse a bitcoin address and check it can receive coins on mutinynet
+pub fn validate_address(address: &str) -> Result<ValidatedAddress, String> {
+    let address = address.trim();
+    let unchecked = Address::<NetworkUnchecked>::from_str(address)
+        .map_err(|e| format!("Invalid bitcoin address {address}: {e}"))?;
+
+    if !unchecked.is_valid_for_network(Network::Signet) {
+        let network = if unchecked.is_valid_for_network(Network::Bitcoin) {
+            "bitcoin mainnet"
+        } else {
+            "regtest"
+        };
+        return Err(format!(
+            "Address {address} is for {network}, only mutinynet (signet) addresses starting with tb1, 2, m or n can be paid"
+        ));
+    }
+
+    let address = unchecked.assume_checked();
+    let address_type = match address.address_type() {
+        Some(address_type) => type_name(address_type),
+        None => "unknown witness program",
+    };
+    Ok(ValidatedAddress {
+        address: address.to_string(),
+        address_type,
+    })
+}
+
+pub fn type_name(address_type: AddressType) -> &'static str {
+    match address_type {
+        AddressType::P2pkh => "P2PKH",
+        AddressType::P2sh => "P2SH",
+        AddressType::P2wpkh => "P2WPKH",
+        AddressType::P2wsh => "P2WSH",
+        AddressType::P2tr => "P2TR",
+        AddressType::P2a => "P2A",
+        _ => "unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_address_types() {
+        for (address, address_type) in [
+            ("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx", "P2WPKH"),
+            (
+                "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7",
+                "P2WSH",
+            ),
+            (
+                "tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c",
+                "P2TR",
+            ),
+            ("2MzQwSSnBHWHqSAqtTVQ6v47XtaisrJa1Vc", "P2SH"),
+            ("mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn", "P2PKH"),
+        ] {
+            let validated = validate_address(address).unwrap();
+            assert_eq!(validated.address, address);
+            assert_eq!(validated.address_type, address_type);
+        }
+    }
+
+    #[test]
+    fn test_normalizes_address() {
+        let validated = validate_address(" TB1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KXPJZSX\n").unwrap();
+        assert_eq!(
+            validated.address,
+            "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"
+        );
+    }
+
+    #[test]
+    fn test_reject_other_networks() {
+        let err = validate_address("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4")
+            .err()
+            .unwrap();
+        assert!(err.contains("bitcoin mainnet"), "{err}");
+
+        let err = validate_address("bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080")
+            .err()
+            .unwrap();
+        assert!(err.contains("regtest"), "{err}");
+    }
+
+    #[test]
+    fn test_reject_typos() {
+        // last character changed, so the checksum no longer matches
+        let err = validate_address("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsy")
+            .err()
+            .unwrap();
+        assert!(err.starts_with("Invalid bitcoin address"), "{err}");
+        assert!(validate_address("not an address").is_err());
+    }
+}
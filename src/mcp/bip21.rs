@@ -0,0 +1,139 @@
+use bitcoin::{Amount, Denomination};
+use std::collections::HashSet;
+
+/// The parts of a `bitcoin:` URI we can pay
+#[derive(Debug, PartialEq)]
+pub struct Bip21Uri {
+    /// the address as written in the uri, still to be validated
+    pub address: String,
+    pub amount_sats: Option<u64>,
+    /// bolt11 invoice to pay instead of the address, for wallets that support it
+    pub lightning: Option<String>,
+}
+
+impl Bip21Uri {
+    /// the amount to pay, `requested` is the amount the caller asked for which
+    /// has to agree with the uri if both are given
+    pub fn amount(&self, requested: Option<u64>) -> Result<Option<u64>, String> {
+        match (requested, self.amount_sats) {
+            (Some(requested), Some(amount)) if requested != amount => Err(format!(
+                "Amount of {requested} sats doesn't match the {amount} sats asked for in the BIP21 URI"
+            )),
+            (requested, amount) => Ok(requested.or(amount)),
+        }
+    }
+}
+
+/// parse a BIP21 `bitcoin:` URI, refusing any `req-` parameter we don't understand
+pub fn parse_bip21(uri: &str) -> Result<Bip21Uri, String> {
+    let uri = uri.trim();
+    let rest = uri
+        .get(..8)
+        .filter(|scheme| scheme.eq_ignore_ascii_case("bitcoin:"))
+        .map(|_| &uri[8..])
+        .ok_or_else(|| format!("{uri} is not a BIP21 URI, it must start with bitcoin:"))?;
+    let (address, query) = rest.split_once('?').unwrap_or((rest, ""));
+    if address.is_empty() {
+        return Err("BIP21 URI has no address".to_string());
+    }
+
+    let mut parsed = Bip21Uri {
+        address: address.to_string(),
+        amount_sats: None,
+        lightning: None,
+    };
+    let mut seen = HashSet::new();
+    for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+        let key = key.to_lowercase();
+        if !seen.insert(key.clone()) {
+            return Err(format!("BIP21 URI has more than one {key} parameter"));
+        }
+        match key.as_str() {
+            "amount" => {
+                let amount = Amount::from_str_in(&value, Denomination::Bitcoin)
+                    .map_err(|e| format!("Invalid amount {value} in BIP21 URI: {e}"))?;
+                if amount == Amount::ZERO {
+                    return Err("BIP21 URI amount must be above zero".to_string());
+                }
+                parsed.amount_sats = Some(amount.to_sat());
+            }
+            "lightning" => parsed.lightning = Some(value.into_owned()),
+            // only shown to the payer by wallets
+            "label" | "message" => {}
+            key if key.starts_with("req-") => {
+                return Err(format!(
+                    "BIP21 URI requires the {key} parameter, which isn't supported"
+                ));
+            }
+            // BIP21 says parameters without req- can be ignored
+            _ => {}
+        }
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+
+    #[test]
+    fn test_parse_bip21() {
+        let uri = parse_bip21(&format!(
+            "BITCOIN:{ADDRESS}?amount=0.0005&label=Test%20wallet&lightning=lntbs1invoice&pj=https://example.com"
+        ))
+        .unwrap();
+        assert_eq!(
+            uri,
+            Bip21Uri {
+                address: ADDRESS.to_string(),
+                amount_sats: Some(50_000),
+                lightning: Some("lntbs1invoice".to_string()),
+            }
+        );
+
+        let uri = parse_bip21(&format!("bitcoin:{ADDRESS}")).unwrap();
+        assert_eq!(uri.amount_sats, None);
+        assert_eq!(uri.lightning, None);
+    }
+
+    #[test]
+    fn test_amount() {
+        let uri = parse_bip21(&format!("bitcoin:{ADDRESS}?amount=0.0005")).unwrap();
+        assert_eq!(uri.amount(None), Ok(Some(50_000)));
+        assert_eq!(uri.amount(Some(50_000)), Ok(Some(50_000)));
+        assert!(uri.amount(Some(1_000)).is_err());
+        let uri = parse_bip21(&format!("bitcoin:{ADDRESS}")).unwrap();
+        assert_eq!(uri.amount(Some(1_000)), Ok(Some(1_000)));
+        assert_eq!(uri.amount(None), Ok(None));
+    }
+
+    #[test]
+    fn test_invalid_bip21() {
+        for (uri, expected) in [
+            (ADDRESS.to_string(), "must start with bitcoin:"),
+            ("bitcoin:?amount=1".to_string(), "has no address"),
+            (
+                format!("bitcoin:{ADDRESS}?amount=1btc"),
+                "Invalid amount 1btc",
+            ),
+            (
+                format!("bitcoin:{ADDRESS}?amount=0.000000001"),
+                "Invalid amount",
+            ),
+            (format!("bitcoin:{ADDRESS}?amount=0"), "must be above zero"),
+            (
+                format!("bitcoin:{ADDRESS}?amount=1&amount=2"),
+                "more than one amount parameter",
+            ),
+            (
+                format!("bitcoin:{ADDRESS}?req-somethingelse=x"),
+                "requires the req-somethingelse parameter, which isn't supported",
+            ),
+        ] {
+            let err = parse_bip21(&uri).err().unwrap();
+            assert!(err.contains(expected), "{uri}: {err}");
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use bitcoin::secp256k1::PublicKey;
+use std::str::FromStr;
+
+/// smallest channel most lightning implementations will accept
+pub const MIN_CHANNEL_CAPACITY: u64 = 20_000;
+/// largest channel allowed without option_support_large_channel
+pub const MAX_CHANNEL_CAPACITY: u64 = 16_777_215;
+
+/// A channel the faucet can be asked to open
+#[derive(Debug, Clone)]
+pub struct ChannelRequest {
+    /// hex encoded compressed public key of the node
+    pub pubkey: String,
+    /// host:port to reach the node on
+    pub host: String,
+    pub capacity: u64,
+    /// sats given to the node when the channel opens
+    pub push_amount: u64,
+}
+
+impl ChannelRequest {
+    /// human readable summary, one field per line
+    pub fn summary(&self) -> String {
+        format!(
+            "Node: {}@{}\nCapacity: {} sats\nPush amount: {} sats",
+            self.pubkey, self.host, self.capacity, self.push_amount
+        )
+    }
+}
+
+/// parse a `pubkey@host:port` node uri and check the channel is one the faucet can open
+pub fn parse_channel_request(
+    node: &str,
+    capacity: u64,
+    push_amount: u64,
+) -> Result<ChannelRequest, String> {
+    let node = node.trim();
+    let Some((pubkey, host)) = node.split_once('@') else {
+        return Err(format!(
+            "Node {node} is missing its address, expected pubkey@host:port"
+        ));
+    };
+    let pubkey =
+        PublicKey::from_str(pubkey).map_err(|e| format!("Invalid node pubkey {pubkey}: {e}"))?;
+    if !is_host_and_port(host) {
+        return Err(format!("Invalid node address {host}, expected host:port"));
+    }
+
+    if !(MIN_CHANNEL_CAPACITY..=MAX_CHANNEL_CAPACITY).contains(&capacity) {
+        return Err(format!(
+            "Channel capacity of {capacity} sats must be between {MIN_CHANNEL_CAPACITY} and {MAX_CHANNEL_CAPACITY} sats"
+        ));
+    }
+    if push_amount >= capacity {
+        return Err(format!(
+            "Push amount of {push_amount} sats must be below the channel capacity of {capacity} sats"
+        ));
+    }
+
+    Ok(ChannelRequest {
+        pubkey: pubkey.to_string(),
+        host: host.to_string(),
+        capacity,
+        push_amount,
+    })
+}
+
+/// a hostname, ipv4 or bracketed ipv6 address followed by a non-zero port
+fn is_host_and_port(value: &str) -> bool {
+    let Some((host, port)) = value.rsplit_once(':') else {
+        return false;
+    };
+    if !matches!(port.parse::<u16>(), Ok(port) if port != 0) {
+        return false;
+    }
+    if let Some(ipv6) = host.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+        return ipv6.parse::<std::net::Ipv6Addr>().is_ok();
+    }
+    !host.is_empty()
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUBKEY: &str = "02eec7245d6b7d2ccb30380bfbe2a3648cd7a942653f5aa340edcea1f283686619";
+
+    #[test]
+    fn test_parse_channel_request() {
+        for host in ["127.0.0.1:9735", "node.example.com:9735", "[::1]:9735"] {
+            let request =
+                parse_channel_request(&format!(" {PUBKEY}@{host} "), 100_000, 1_000).unwrap();
+            assert_eq!(request.pubkey, PUBKEY);
+            assert_eq!(request.host, host);
+        }
+    }
+
+    #[test]
+    fn test_invalid_node() {
+        let err = parse_channel_request(PUBKEY, 100_000, 0).unwrap_err();
+        assert!(err.contains("missing its address"), "{err}");
+        let err = parse_channel_request("02abcd@127.0.0.1:9735", 100_000, 0).unwrap_err();
+        assert!(err.starts_with("Invalid node pubkey 02abcd"), "{err}");
+        for host in [
+            "127.0.0.1",
+            "127.0.0.1:0",
+            "127.0.0.1:99999",
+            ":9735",
+            "::1:9735",
+            "a b:1",
+        ] {
+            let err = parse_channel_request(&format!("{PUBKEY}@{host}"), 100_000, 0).unwrap_err();
+            assert_eq!(
+                err,
+                format!("Invalid node address {host}, expected host:port")
+            );
+        }
+    }
+
+    #[test]
+    fn test_capacity_bounds() {
+        let node = format!("{PUBKEY}@127.0.0.1:9735");
+        let err = parse_channel_request(&node, MIN_CHANNEL_CAPACITY - 1, 0).unwrap_err();
+        assert_eq!(
+            err,
+            "Channel capacity of 19999 sats must be between 20000 and 16777215 sats"
+        );
+        assert!(parse_channel_request(&node, MAX_CHANNEL_CAPACITY + 1, 0).is_err());
+        assert!(parse_channel_request(&node, MAX_CHANNEL_CAPACITY, 0).is_ok());
+        let err = parse_channel_request(&node, 100_000, 100_000).unwrap_err();
+        assert_eq!(
+            err,
+            "Push amount of 100000 sats must be below the channel capacity of 100000 sats"
+        );
+    }
+}
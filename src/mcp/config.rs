@@ -0,0 +1,43 @@
+use crate::mcp::policy::LimitsConfig;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+pub const DEFAULT_FAUCET_URL: &str = "https://faucet.mutinynet.com";
+pub const DEFAULT_GITHUB_URL: &str = "https://github.com";
+pub const DEFAULT_ESPLORA_URL: &str = "https://mutinynet.com/api";
+
+/// Settings read from `~/.config/mcp/mutinynet/config.toml`, command line flags take precedence
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// base url of the faucet, e.g. a self-hosted fork
+    pub faucet_url: Option<Url>,
+    /// base url of github, used for the device login flow
+    pub github_url: Option<Url>,
+    /// base url of the esplora api used to look up transactions
+    pub esplora_url: Option<Url>,
+    /// spend limits for faucet payouts
+    pub limits: LimitsConfig,
+    /// ask the user to approve every payout through MCP elicitation
+    pub confirm_payments: bool,
+}
+
+impl Config {
+    /// load the config file, a missing file is the same as an empty one
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config {}: {e}", path.display()))?;
+        toml::from_str(&contents)
+            .map_err(|e| format!("Failed to parse config {}: {e}", path.display()))
+    }
+}
+
+pub fn get_config_location() -> PathBuf {
+    home::home_dir()
+        .expect("No Home Directory found")
+        .join(".config/mcp/mutinynet/config.toml")
+}
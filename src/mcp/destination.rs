@@ -0,0 +1,95 @@
+use bitcoin::Address;
+use bitcoin::address::NetworkUnchecked;
+use serde::Serialize;
+use std::str::FromStr;
+
+/// What a payment destination looks like, worked out from its prefix alone,
+/// the tool it is paid with does the full validation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DestinationType {
+    Address,
+    Bip21,
+    Bolt11,
+    Bolt12,
+    LightningAddress,
+    Lnurl,
+}
+
+impl DestinationType {
+    /// human readable name
+    pub fn name(self) -> &'static str {
+        match self {
+            DestinationType::Address => "bitcoin address",
+            DestinationType::Bip21 => "BIP21 URI",
+            DestinationType::Bolt11 => "bolt11 invoice",
+            DestinationType::Bolt12 => "bolt12 offer",
+            DestinationType::LightningAddress => "lightning address",
+            DestinationType::Lnurl => "LNURL",
+        }
+    }
+}
+
+/// work out what kind of destination a string is
+pub fn classify(destination: &str) -> Result<DestinationType, String> {
+    let trimmed = destination.trim();
+    let lower = trimmed.to_lowercase();
+    if lower.starts_with("bitcoin:") {
+        return Ok(DestinationType::Bip21);
+    }
+    let lower = lower.strip_prefix("lightning:").unwrap_or(&lower);
+    // checked in the same order as `lnurl::parse_target`, a lightning address can start with ln
+    let destination_type = if lower.starts_with("lnurl1") {
+        DestinationType::Lnurl
+    } else if lower.contains('@') {
+        DestinationType::LightningAddress
+    } else if lower.starts_with("lno1") {
+        DestinationType::Bolt12
+    } else if lower.starts_with("ln") {
+        DestinationType::Bolt11
+    } else if Address::<NetworkUnchecked>::from_str(trimmed).is_ok() {
+        DestinationType::Address
+    } else {
+        return Err(format!(
+            "Couldn't tell what {trimmed} is, expected a bitcoin address, BIP21 URI, bolt11 invoice, bolt12 offer, lightning address or LNURL"
+        ));
+    };
+    Ok(destination_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        for (destination, expected) in [
+            (
+                "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx",
+                DestinationType::Address,
+            ),
+            (
+                "TB1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KXPJZSX",
+                DestinationType::Address,
+            ),
+            (
+                "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+                DestinationType::Address,
+            ),
+            (
+                "BITCOIN:tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx?amount=1",
+                DestinationType::Bip21,
+            ),
+            ("lntbs1invoice", DestinationType::Bolt11),
+            ("lightning:LNTBS1INVOICE", DestinationType::Bolt11),
+            ("lno1offer", DestinationType::Bolt12),
+            ("alice@example.com", DestinationType::LightningAddress),
+            ("lnbits@example.com", DestinationType::LightningAddress),
+            ("lightning:LNURL1DP68GURN8GHJ7", DestinationType::Lnurl),
+        ] {
+            assert_eq!(classify(destination), Ok(expected), "{destination}");
+        }
+        let err = classify("hello").err().unwrap();
+        assert!(err.starts_with("Couldn't tell what hello is"), "{err}");
+    }
+}
@@ -0,0 +1,63 @@
+use crate::mcp::session::{RequestError, Session};
+use crate::mcp::types::{ElicitAction, ElicitRequest, ElicitResult};
+use serde_json::{Value, json};
+use std::time::Duration;
+
+/// ask the user to approve an action through an `elicitation/create` request, waiting up to
+/// `timeout` for the answer, the error explains why the action must not go ahead
+pub async fn confirm(session: &Session, message: String, timeout: Duration) -> Result<(), String> {
+    if session.client_capabilities().elicitation.is_none() {
+        return Err(
+            "Payments need to be confirmed, but this client doesn't support elicitation, so the payment was refused"
+                .to_string(),
+        );
+    }
+
+    let params = ElicitRequest {
+        message,
+        requested_schema: json!({
+            "type": "object",
+            "properties": {
+                "approve": {
+                    "type": "boolean",
+                    "title": "Approve payment",
+                    "description": "Pay this from the mutinynet faucet",
+                }
+            },
+            "required": ["approve"],
+        }),
+    };
+    let params = serde_json::to_value(params).unwrap();
+    let response = match session.request("elicitation/create", params, timeout).await {
+        Ok(response) => response,
+        // nobody answered, which is as good as a no
+        Err(RequestError::TimedOut) => {
+            return Err(format!(
+                "Payment wasn't confirmed within {}s, so it was refused",
+                timeout.as_secs()
+            ));
+        }
+        Err(RequestError::Disconnected) => {
+            return Err(
+                "Failed to confirm the payment with the client: the client disconnected"
+                    .to_string(),
+            );
+        }
+        Err(RequestError::Response(error)) => {
+            let message = error.get("message").and_then(Value::as_str).unwrap_or("");
+            return Err(format!(
+                "Failed to confirm the payment with the client: {message}"
+            ));
+        }
+    };
+    let result = serde_json::from_value::<ElicitResult>(response)
+        .map_err(|e| format!("Failed to confirm the payment with the client: {e}"))?;
+
+    match result.action {
+        ElicitAction::Accept if result.content.get("approve") == Some(&json!(true)) => Ok(()),
+        ElicitAction::Accept | ElicitAction::Decline => {
+            Err("Payment declined by the user".to_string())
+        }
+        ElicitAction::Cancel => Err("Payment cancelled by the user".to_string()),
+    }
+}
@@ -0,0 +1,258 @@
+use crate::mcp::utilities::join_url;
+use chrono::{DateTime, Utc};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use rpc_router::RpcResource;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use url::Url;
+
+/// Client for an Esplora http api, used to look up mutinynet transactions
+#[derive(Clone, RpcResource)]
+pub struct EsploraClient {
+    client: Client,
+    base_url: Url,
+}
+
+#[derive(Debug)]
+pub enum EsploraError {
+    /// esplora doesn't know about the transaction, block or address
+    NotFound,
+    /// the request never got a response
+    Request,
+    /// esplora responded with an error
+    Status(StatusCode, String),
+    /// the response body could not be decoded
+    Decode,
+}
+
+impl std::fmt::Display for EsploraError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EsploraError::NotFound => write!(f, "Not found"),
+            EsploraError::Request => write!(f, "Error making request to esplora"),
+            EsploraError::Status(status, text) => write!(f, "Esplora error ({status}): {text}"),
+            EsploraError::Decode => write!(f, "Error decoding esplora response"),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Transaction {
+    pub txid: String,
+    pub weight: u64,
+    /// in sats
+    pub fee: u64,
+    pub vin: Vec<TxIn>,
+    pub vout: Vec<TxOut>,
+    pub status: TxStatus,
+}
+
+impl Transaction {
+    /// fee rate in sat/vB
+    pub fn fee_rate(&self) -> f64 {
+        self.fee as f64 / self.weight.div_ceil(4) as f64
+    }
+
+    /// sats the transaction moved into the address, negative if it spent more from it than it received
+    pub fn net_value(&self, address: &str) -> i64 {
+        let received: u64 = self
+            .vout
+            .iter()
+            .filter(|out| out.scriptpubkey_address.as_deref() == Some(address))
+            .map(|out| out.value)
+            .sum();
+        let spent: u64 = self
+            .vin
+            .iter()
+            .filter_map(|input| input.prevout.as_ref())
+            .filter(|out| out.scriptpubkey_address.as_deref() == Some(address))
+            .map(|out| out.value)
+            .sum();
+        received as i64 - spent as i64
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TxIn {
+    /// the output being spent, missing for coinbase inputs
+    pub prevout: Option<TxOut>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TxOut {
+    pub scriptpubkey_address: Option<String>,
+    pub scriptpubkey_type: String,
+    /// in sats
+    pub value: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TxStatus {
+    pub confirmed: bool,
+    pub block_height: Option<u64>,
+    pub block_hash: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddressInfo {
+    pub chain_stats: AddressStats,
+    pub mempool_stats: AddressStats,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddressStats {
+    pub funded_txo_sum: u64,
+    pub spent_txo_sum: u64,
+    pub tx_count: u64,
+}
+
+impl AddressStats {
+    /// change in balance, the mempool can spend more than it funds
+    pub fn balance(&self) -> i64 {
+        self.funded_txo_sum as i64 - self.spent_txo_sum as i64
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Utxo {
+    pub txid: String,
+    pub vout: u32,
+    pub value: u64,
+    pub status: TxStatus,
+}
+
+#[derive(Debug, Deserialize)]
+struct Block {
+    id: String,
+    height: u64,
+    /// unix time the block was mined
+    timestamp: i64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MempoolStats {
+    /// number of transactions
+    pub count: u64,
+    pub vsize: u64,
+    /// in sats
+    pub total_fee: u64,
+}
+
+/// The latest block and what is waiting to be mined on top of it
+#[derive(Debug, Serialize)]
+pub struct ChainTip {
+    pub height: u64,
+    pub hash: String,
+    pub timestamp: DateTime<Utc>,
+    pub mempool: MempoolStats,
+}
+
+/// Fee rate needed to confirm within a number of blocks
+#[derive(Debug, Serialize)]
+pub struct FeeEstimate {
+    pub target_blocks: u16,
+    pub sat_per_vbyte: f64,
+}
+
+impl EsploraClient {
+    pub fn new(base_url: Url) -> Self {
+        EsploraClient {
+            client: Client::new(),
+            base_url,
+        }
+    }
+
+    pub async fn get_transaction(&self, txid: &str) -> Result<Transaction, EsploraError> {
+        self.get_json(&format!("tx/{txid}")).await
+    }
+
+    pub async fn get_address_info(&self, address: &str) -> Result<AddressInfo, EsploraError> {
+        self.get_json(&format!("address/{address}")).await
+    }
+
+    pub async fn get_address_utxos(&self, address: &str) -> Result<Vec<Utxo>, EsploraError> {
+        self.get_json(&format!("address/{address}/utxo")).await
+    }
+
+    /// the address's mempool transactions followed by its most recent confirmed ones
+    pub async fn get_address_transactions(
+        &self,
+        address: &str,
+    ) -> Result<Vec<Transaction>, EsploraError> {
+        self.get_json(&format!("address/{address}/txs")).await
+    }
+
+    /// height of the current chain tip
+    pub async fn get_tip_height(&self) -> Result<u64, EsploraError> {
+        let resp = self.get("blocks/tip/height").await?;
+        let text = resp.text().await.map_err(|_| EsploraError::Decode)?;
+        text.trim().parse().map_err(|_| EsploraError::Decode)
+    }
+
+    pub async fn get_chain_tip(&self) -> Result<ChainTip, EsploraError> {
+        let resp = self.get("blocks/tip/hash").await?;
+        let hash = resp.text().await.map_err(|_| EsploraError::Decode)?;
+        // look the block up by hash so the height and hash are for the same block
+        let block: Block = self.get_json(&format!("block/{}", hash.trim())).await?;
+        let mempool = self.get_json("mempool").await?;
+        Ok(ChainTip {
+            height: block.height,
+            hash: block.id,
+            timestamp: DateTime::from_timestamp(block.timestamp, 0).ok_or(EsploraError::Decode)?,
+            mempool,
+        })
+    }
+
+    /// fee estimates ordered by confirmation target, soonest first
+    pub async fn get_fee_estimates(&self) -> Result<Vec<FeeEstimate>, EsploraError> {
+        let estimates: HashMap<String, f64> = self.get_json("fee-estimates").await?;
+        let mut estimates = estimates
+            .into_iter()
+            .map(|(target, sat_per_vbyte)| {
+                let target_blocks = target.parse().map_err(|_| EsploraError::Decode)?;
+                Ok(FeeEstimate {
+                    target_blocks,
+                    sat_per_vbyte,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        estimates.sort_by_key(|estimate| estimate.target_blocks);
+        Ok(estimates)
+    }
+
+    /// submit a hex encoded transaction to the mempool, returning its txid
+    pub async fn broadcast(&self, tx_hex: &str) -> Result<String, EsploraError> {
+        let request = self
+            .client
+            .post(join_url(&self.base_url, "tx"))
+            .body(tx_hex.to_string());
+        let resp = self.send(request).await?;
+        let txid = resp.text().await.map_err(|_| EsploraError::Decode)?;
+        Ok(txid.trim().to_string())
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T, EsploraError> {
+        let resp = self.get(path).await?;
+        resp.json().await.map_err(|_| EsploraError::Decode)
+    }
+
+    async fn get(&self, path: &str) -> Result<Response, EsploraError> {
+        self.send(self.client.get(join_url(&self.base_url, path)))
+            .await
+    }
+
+    /// send a request, turning error statuses into an `EsploraError`
+    async fn send(&self, request: RequestBuilder) -> Result<Response, EsploraError> {
+        let resp = request.send().await.map_err(|_| EsploraError::Request)?;
+        let status = resp.status();
+        if status == StatusCode::NOT_FOUND {
+            return Err(EsploraError::NotFound);
+        }
+        if !status.is_success() {
+            let text = resp.text().await.map_err(|_| EsploraError::Decode)?;
+            return Err(EsploraError::Status(status, text));
+        }
+        Ok(resp)
+    }
+}
@@ -0,0 +1,249 @@
+use crate::mcp::channel::ChannelRequest;
+use crate::mcp::utilities::join_url;
+use reqwest::{Client, Response, StatusCode};
+use rpc_router::RpcResource;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use url::Url;
+
+const GH_CLIENT_ID: &str = "Ov23liIa6qfR9KtYHwUF";
+const GH_SCOPE: &str = "user:email";
+
+/// Client for the mutinynet faucet api, and the github device flow used to log in to it
+#[derive(Clone, RpcResource)]
+pub struct FaucetClient {
+    client: Client,
+    base_url: Url,
+    github_url: Url,
+}
+
+#[derive(Debug)]
+pub enum FaucetError {
+    /// the bearer token is missing, expired or revoked
+    Unauthorized,
+    /// the request never got a response
+    Request,
+    /// the faucet responded with an error
+    Status(StatusCode, String),
+    /// the response body could not be decoded
+    Decode,
+}
+
+impl std::fmt::Display for FaucetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FaucetError::Unauthorized => write!(f, "Not logged in"),
+            FaucetError::Request => write!(f, "Error making request"),
+            FaucetError::Status(status, text) => write!(f, "Error ({status}): {text}"),
+            FaucetError::Decode => write!(f, "Error decoding response"),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct DeviceLoginResponse {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub expires_in: u64,
+    pub interval: u64,
+}
+
+#[derive(Deserialize)]
+struct AccessTokenResponse {
+    access_token: String,
+}
+
+#[derive(Deserialize)]
+struct DeviceReturn {
+    token: String,
+}
+
+#[derive(Deserialize)]
+pub struct LightningResponse {
+    pub payment_hash: String,
+}
+
+#[derive(Deserialize)]
+pub struct OnChainResponse {
+    pub txid: String,
+}
+
+#[derive(Deserialize)]
+pub struct ChannelResponse {
+    /// the channel's funding transaction
+    pub txid: String,
+}
+
+impl FaucetClient {
+    pub fn new(base_url: Url, github_url: Url) -> Self {
+        FaucetClient {
+            client: Client::new(),
+            base_url,
+            github_url,
+        }
+    }
+
+    /// start the github device flow, the user has to enter the returned code
+    pub async fn request_device_code(&self) -> Result<DeviceLoginResponse, FaucetError> {
+        let resp = self
+            .client
+            .post(join_url(&self.github_url, "login/device/code"))
+            .json(&json!({
+                "client_id": GH_CLIENT_ID,
+                "scope": GH_SCOPE,
+            }))
+            .header("Content-Type", "application/json")
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .map_err(|_| FaucetError::Request)?;
+        resp.json().await.map_err(|_| FaucetError::Decode)
+    }
+
+    /// check whether the device code has been authorized, returning a faucet bearer token if so
+    pub async fn poll_device_authorization(&self, device_code: &str) -> Option<String> {
+        let res = self
+            .client
+            .post(join_url(&self.github_url, "login/oauth/access_token"))
+            .json(&json!({
+                "client_id": GH_CLIENT_ID,
+                "device_code": device_code,
+                "grant_type": "urn:ietf:params:oauth:grant-type:device_code"
+            }))
+            .header("Content-Type", "application/json")
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .ok()?;
+        let access = res.json::<AccessTokenResponse>().await.ok()?;
+        let res = self
+            .client
+            .post(join_url(&self.base_url, "auth/github/device"))
+            .json(&json!({
+                "code": access.access_token,
+            }))
+            .header("Content-Type", "application/json")
+            .send()
+            .await
+            .ok()?;
+        let device = res.json::<DeviceReturn>().await.ok()?;
+        Some(device.token)
+    }
+
+    /// url and body of the request `pay_lightning` makes
+    pub fn lightning_request(&self, bolt11: &str) -> (Url, Value) {
+        (
+            join_url(&self.base_url, "api/lightning"),
+            json!({ "bolt11": bolt11 }),
+        )
+    }
+
+    /// pay a bolt11 invoice from the faucet's lightning node
+    pub async fn pay_lightning(
+        &self,
+        token: &str,
+        bolt11: &str,
+    ) -> Result<LightningResponse, FaucetError> {
+        let (url, body) = self.lightning_request(bolt11);
+        self.post_authed(token, url, body).await
+    }
+
+    /// url and body of the request `pay_bolt12` makes
+    pub fn bolt12_request(&self, offer: &str, sats: u64) -> (Url, Value) {
+        (
+            join_url(&self.base_url, "api/bolt12"),
+            json!({
+                "offer": offer,
+                "amount": sats,
+            }),
+        )
+    }
+
+    /// pay a bolt12 offer from the faucet's lightning node
+    pub async fn pay_bolt12(
+        &self,
+        token: &str,
+        offer: &str,
+        sats: u64,
+    ) -> Result<LightningResponse, FaucetError> {
+        let (url, body) = self.bolt12_request(offer, sats);
+        self.post_authed(token, url, body).await
+    }
+
+    /// url and body of the request `pay_onchain` makes
+    pub fn onchain_request(&self, sats: u64, address: &str) -> (Url, Value) {
+        (
+            join_url(&self.base_url, "api/onchain"),
+            json!({
+                "sats": sats,
+                "address": address,
+            }),
+        )
+    }
+
+    /// send an on-chain payout from the faucet's wallet
+    pub async fn pay_onchain(
+        &self,
+        token: &str,
+        sats: u64,
+        address: &str,
+    ) -> Result<OnChainResponse, FaucetError> {
+        let (url, body) = self.onchain_request(sats, address);
+        self.post_authed(token, url, body).await
+    }
+
+    /// url and body of the request `open_channel` makes
+    pub fn channel_request(&self, channel: &ChannelRequest) -> (Url, Value) {
+        (
+            join_url(&self.base_url, "api/channel"),
+            json!({
+                "capacity": channel.capacity,
+                "push_amount": channel.push_amount,
+                "pubkey": channel.pubkey,
+                "host": channel.host,
+            }),
+        )
+    }
+
+    /// open a channel from the faucet's lightning node
+    pub async fn open_channel(
+        &self,
+        token: &str,
+        channel: &ChannelRequest,
+    ) -> Result<ChannelResponse, FaucetError> {
+        let (url, body) = self.channel_request(channel);
+        self.post_authed(token, url, body).await
+    }
+
+    async fn post_authed<T: DeserializeOwned>(
+        &self,
+        token: &str,
+        url: Url,
+        body: Value,
+    ) -> Result<T, FaucetError> {
+        let resp = self
+            .client
+            .post(url)
+            .json(&body)
+            .header("Content-Type", "application/json")
+            .header("Authorization", format!("Bearer {token}"))
+            .send()
+            .await
+            .map_err(|_| FaucetError::Request)?;
+        decode_response(resp).await
+    }
+}
+
+async fn decode_response<T: DeserializeOwned>(resp: Response) -> Result<T, FaucetError> {
+    let status = resp.status();
+    if !status.is_success() {
+        if status == StatusCode::UNAUTHORIZED {
+            return Err(FaucetError::Unauthorized);
+        }
+        let text = resp.text().await.map_err(|_| FaucetError::Decode)?;
+        return Err(FaucetError::Status(status, text));
+    }
+    resp.json().await.map_err(|_| FaucetError::Decode)
+}
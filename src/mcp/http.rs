@@ -0,0 +1,308 @@
+use crate::mcp::server::{accept_message, handle_message, process_message};
+use crate::mcp::session::Session;
+use crate::mcp::transcript::Transcript;
+use axum::Json;
+use axum::extract::{Request, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::middleware::{self, Next};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use rpc_router::Router;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+use tokio::task::JoinHandle;
+use tokio_stream::{Stream, StreamExt};
+use url::Url;
+use uuid::Uuid;
+
+const SESSION_HEADER: &str = "mcp-session-id";
+/// hosts that browsers on this machine use for the server, allowed without `--http-allowed-origin`
+const LOCAL_HOSTS: [&str; 3] = ["localhost", "127.0.0.1", "[::1]"];
+
+/// Options for the streamable HTTP transport
+pub struct HttpOptions {
+    /// origins allowed besides localhost, e.g. `https://app.example.com`
+    pub allowed_origins: Vec<String>,
+    /// sessions with nothing in flight and no SSE listener are dropped after this long
+    pub session_timeout: Duration,
+}
+
+#[derive(Clone)]
+struct HttpState {
+    router: Router,
+    transcript: Option<Transcript>,
+    allowed_origins: Arc<Vec<String>>,
+    session_timeout: Duration,
+    sessions: Arc<Mutex<HashMap<String, HttpSession>>>,
+}
+
+#[derive(Clone)]
+struct HttpSession {
+    session: Session,
+    /// server-initiated messages not tied to a request, queued until a `GET` stream takes them
+    events: Arc<AsyncMutex<UnboundedReceiver<Value>>>,
+    last_seen: Arc<Mutex<Instant>>,
+}
+
+impl HttpSession {
+    fn touch(&self) {
+        *self.last_seen.lock().unwrap() = Instant::now();
+    }
+
+    /// idle sessions have no requests in flight and nobody listening on `GET`
+    fn is_idle(&self, timeout: Duration) -> bool {
+        self.last_seen.lock().unwrap().elapsed() >= timeout
+            && !self.session.has_in_flight_requests()
+            && self.events.try_lock().is_ok()
+    }
+}
+
+impl HttpState {
+    fn create_session(&self) -> (String, HttpSession) {
+        self.evict_idle_sessions();
+        let session_id = Uuid::new_v4().to_string();
+        let (session, events) = Session::new();
+        let http_session = HttpSession {
+            session,
+            events: Arc::new(AsyncMutex::new(events)),
+            last_seen: Arc::new(Mutex::new(Instant::now())),
+        };
+        self.sessions
+            .lock()
+            .unwrap()
+            .insert(session_id.clone(), http_session.clone());
+        (session_id, http_session)
+    }
+
+    fn get_session(
+        &self,
+        headers: &HeaderMap,
+    ) -> Result<(String, HttpSession), (StatusCode, &'static str)> {
+        let Some(session_id) = headers.get(SESSION_HEADER).and_then(|v| v.to_str().ok()) else {
+            return Err((StatusCode::BAD_REQUEST, "Missing Mcp-Session-Id header"));
+        };
+        let mut sessions = self.sessions.lock().unwrap();
+        match sessions.get(session_id) {
+            Some(http_session) if http_session.is_idle(self.session_timeout) => {
+                http_session.session.close_pending_requests();
+                sessions.remove(session_id);
+                Err((StatusCode::NOT_FOUND, "Unknown session"))
+            }
+            Some(http_session) => {
+                http_session.touch();
+                Ok((session_id.to_string(), http_session.clone()))
+            }
+            None => Err((StatusCode::NOT_FOUND, "Unknown session")),
+        }
+    }
+
+    /// drop sessions that were abandoned without a `DELETE`
+    fn evict_idle_sessions(&self) {
+        self.sessions.lock().unwrap().retain(|_, http_session| {
+            let idle = http_session.is_idle(self.session_timeout);
+            if idle {
+                http_session.session.close_pending_requests();
+            }
+            !idle
+        });
+    }
+
+    fn is_allowed_origin(&self, origin: &str) -> bool {
+        if self.allowed_origins.iter().any(|allowed| allowed == origin) {
+            return true;
+        }
+        Url::parse(origin)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .is_some_and(|host| LOCAL_HOSTS.contains(&host.as_str()))
+    }
+
+    fn record(&self, message: &Value) {
+        if let Some(transcript) = &self.transcript {
+            transcript.record(message);
+        }
+    }
+}
+
+/// serve the MCP router over the streamable HTTP transport at `/mcp`
+pub async fn serve(
+    addr: SocketAddr,
+    router: Router,
+    transcript: Option<Transcript>,
+    options: HttpOptions,
+) -> std::io::Result<()> {
+    let state = HttpState {
+        router,
+        transcript,
+        allowed_origins: Arc::new(options.allowed_origins),
+        session_timeout: options.session_timeout,
+        sessions: Arc::new(Mutex::new(HashMap::new())),
+    };
+    let app = axum::Router::new()
+        .route(
+            "/mcp",
+            post(handle_post).get(handle_get).delete(handle_delete),
+        )
+        .layer(middleware::from_fn_with_state(state.clone(), check_origin))
+        .with_state(state);
+    let listener = TcpListener::bind(addr).await?;
+    axum::serve(listener, app).await
+}
+
+/// refuse browser requests from other sites, which could otherwise reach this server through
+/// DNS rebinding and spend from the faucet, clients that aren't browsers send no `Origin`
+async fn check_origin(State(state): State<HttpState>, request: Request, next: Next) -> Response {
+    if let Some(origin) = request.headers().get(header::ORIGIN) {
+        let allowed = origin
+            .to_str()
+            .is_ok_and(|origin| state.is_allowed_origin(origin));
+        if !allowed {
+            return (StatusCode::FORBIDDEN, "Origin not allowed").into_response();
+        }
+    }
+    next.run(request).await
+}
+
+/// client to server messages, a new session is created on `initialize`
+async fn handle_post(
+    State(state): State<HttpState>,
+    headers: HeaderMap,
+    Json(message): Json<Value>,
+) -> Response {
+    let is_initialize = message.get("method").and_then(Value::as_str) == Some("initialize");
+    let (session_id, http_session) = if is_initialize && !headers.contains_key(SESSION_HEADER) {
+        state.create_session()
+    } else {
+        match state.get_session(&headers) {
+            Ok(session) => session,
+            Err(error) => return error.into_response(),
+        }
+    };
+    state.record(&message);
+
+    // requests from clients that accept SSE get their own stream, carrying the progress, logs
+    // and elicitations for that request followed by its response
+    let is_request = message.get("id").is_some() && message.get("method").is_some();
+    if is_request && accepts_event_stream(&headers) {
+        let (outgoing, events) = unbounded_channel();
+        let request_session = http_session.session.with_outgoing(outgoing);
+        let router = state.router.clone();
+        accept_message(&http_session.session, &message);
+        let handler = tokio::spawn(async move {
+            let response = process_message(&router, &request_session, message).await;
+            http_session.touch();
+            response
+        });
+        let stream = RequestEvents {
+            events,
+            handler,
+            response: None,
+        }
+        .map(move |message| {
+            state.record(&message);
+            Ok::<_, Infallible>(Event::default().data(message.to_string()))
+        });
+        return ([(SESSION_HEADER, session_id)], Sse::new(stream)).into_response();
+    }
+
+    let response = handle_message(&state.router, &http_session.session, message).await;
+    http_session.touch();
+    match response {
+        Some(response) => {
+            state.record(&response);
+            ([(SESSION_HEADER, session_id)], Json(response)).into_response()
+        }
+        None => (StatusCode::ACCEPTED, [(SESSION_HEADER, session_id)]).into_response(),
+    }
+}
+
+/// SSE stream of server-initiated messages for a session that aren't sent on a request's stream
+async fn handle_get(State(state): State<HttpState>, headers: HeaderMap) -> Response {
+    let http_session = match state.get_session(&headers) {
+        Ok((_, http_session)) => http_session,
+        Err(error) => return error.into_response(),
+    };
+    // messages are only delivered once, so only one listener at a time
+    let Ok(events) = http_session.events.clone().try_lock_owned() else {
+        return (StatusCode::CONFLICT, "Session already has an SSE stream").into_response();
+    };
+    let stream = SessionEvents { events }.map(move |message| {
+        state.record(&message);
+        Ok::<_, Infallible>(Event::default().data(message.to_string()))
+    });
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// explicit session termination by the client
+async fn handle_delete(State(state): State<HttpState>, headers: HeaderMap) -> Response {
+    match state.get_session(&headers) {
+        Ok((session_id, http_session)) => {
+            http_session.session.close_pending_requests();
+            state.sessions.lock().unwrap().remove(&session_id);
+            StatusCode::OK.into_response()
+        }
+        Err(error) => error.into_response(),
+    }
+}
+
+fn accepts_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .any(|v| v.contains("text/event-stream"))
+}
+
+/// messages queued for a session's `GET` stream, held by the stream while it is open
+struct SessionEvents {
+    events: OwnedMutexGuard<UnboundedReceiver<Value>>,
+}
+
+impl Stream for SessionEvents {
+    type Item = Value;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Value>> {
+        self.events.poll_recv(cx)
+    }
+}
+
+/// messages sent while handling a request, ending with its response
+struct RequestEvents {
+    events: UnboundedReceiver<Value>,
+    handler: JoinHandle<Option<Value>>,
+    /// the response, once the handler has finished
+    response: Option<Option<Value>>,
+}
+
+impl Stream for RequestEvents {
+    type Item = Value;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Value>> {
+        if let Poll::Ready(Some(message)) = self.events.poll_recv(cx) {
+            return Poll::Ready(Some(message));
+        }
+        if self.response.is_none() {
+            let Poll::Ready(response) = Pin::new(&mut self.handler).poll(cx) else {
+                return Poll::Pending;
+            };
+            self.response = Some(response.ok().flatten());
+        }
+        // anything sent before the handler finished goes out ahead of the response
+        if let Ok(message) = self.events.try_recv() {
+            return Poll::Ready(Some(message));
+        }
+        Poll::Ready(self.response.as_mut().and_then(Option::take))
+    }
+}
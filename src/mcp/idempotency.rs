@@ -0,0 +1,64 @@
+use crate::mcp::ledger::{self, Payout};
+use crate::mcp::policy::PayoutKind;
+use std::collections::HashSet;
+use std::sync::{LazyLock, Mutex};
+
+/// keys of payouts that are being made right now
+static IN_FLIGHT: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// Outcome of claiming an idempotency key before a payout
+pub enum Claim {
+    /// a payout was already made with this key, it must not be made again
+    Paid(Payout),
+    /// no payout has been made with this key, hold on to the guard until it is recorded,
+    /// there is nothing to guard if the client didn't send a key
+    New(Option<KeyGuard>),
+}
+
+/// Marks a key as in flight until dropped
+pub struct KeyGuard {
+    key: String,
+}
+
+impl Drop for KeyGuard {
+    fn drop(&mut self) {
+        IN_FLIGHT.lock().unwrap().remove(&self.key);
+    }
+}
+
+/// check the ledger for an earlier payout with this key, the error explains why the
+/// payout can't go ahead
+pub fn claim(
+    key: Option<&str>,
+    kind: PayoutKind,
+    destination: &str,
+    sats: u64,
+) -> Result<Claim, String> {
+    let Some(key) = key else {
+        return Ok(Claim::New(None));
+    };
+    if !IN_FLIGHT.lock().unwrap().insert(key.to_string()) {
+        return Err(format!(
+            "A payout with idempotency key {key} is already in progress"
+        ));
+    }
+    let guard = KeyGuard {
+        key: key.to_string(),
+    };
+
+    let payouts =
+        ledger::read_payouts().map_err(|e| format!("Failed to read payout history: {e}"))?;
+    let Some(payout) = payouts
+        .into_iter()
+        .find(|payout| payout.idempotency_key.as_deref() == Some(key))
+    else {
+        return Ok(Claim::New(Some(guard)));
+    };
+    if payout.kind != kind || payout.destination != destination || payout.sats != sats {
+        return Err(format!(
+            "Idempotency key {key} was already used for a different payout of {} sats to {}",
+            payout.sats, payout.destination
+        ));
+    }
+    Ok(Claim::Paid(payout))
+}
@@ -0,0 +1,149 @@
+use lightning_invoice::{Bolt11Invoice, Bolt11InvoiceDescriptionRef, Currency};
+use std::str::FromStr;
+
+/// The parts of a bolt11 invoice worth showing the agent before and after paying it
+pub struct DecodedInvoice {
+    /// the invoice without whitespace or a `lightning:` prefix
+    pub invoice: String,
+    pub amount_sats: u64,
+    pub description: String,
+    pub payment_hash: String,
+}
+
+impl DecodedInvoice {
+    /// human readable summary, one field per line
+    pub fn summary(&self) -> String {
+        format!(
+            "Amount: {} sats\nDescription: {}\nPayment hash: {}",
+            self.amount_sats, self.description, self.payment_hash
+        )
+    }
+}
+
+/// parse a bolt11 invoice and check the faucet is able to pay it, spend limits are left to the policy
+pub fn decode_invoice(invoice: &str) -> Result<DecodedInvoice, String> {
+    let invoice = invoice.trim();
+    let invoice = invoice
+        .strip_prefix("lightning:")
+        .or_else(|| invoice.strip_prefix("LIGHTNING:"))
+        .unwrap_or(invoice);
+    let invoice =
+        Bolt11Invoice::from_str(invoice).map_err(|e| format!("Invalid bolt11 invoice: {e}"))?;
+
+    if invoice.currency() != Currency::Signet {
+        return Err(format!(
+            "Invoice is for {}, only mutinynet (signet, lntbs) invoices can be paid",
+            currency_name(invoice.currency())
+        ));
+    }
+    if invoice.is_expired() {
+        return Err("Invoice has expired, ask for a new one".to_string());
+    }
+    let Some(amount_msats) = invoice.amount_milli_satoshis() else {
+        return Err(
+            "Invoice has no amount, the faucet can only pay invoices with an amount".to_string(),
+        );
+    };
+    let amount_sats = amount_msats.div_ceil(1_000);
+
+    let description = match invoice.description() {
+        Bolt11InvoiceDescriptionRef::Direct(description) => description.to_string(),
+        Bolt11InvoiceDescriptionRef::Hash(hash) => format!("(description hash {})", hash.0),
+    };
+    Ok(DecodedInvoice {
+        invoice: invoice.to_string(),
+        amount_sats,
+        description,
+        payment_hash: invoice.payment_hash().to_string(),
+    })
+}
+
+fn currency_name(currency: Currency) -> &'static str {
+    match currency {
+        Currency::Bitcoin => "bitcoin mainnet (lnbc)",
+        Currency::BitcoinTestnet => "testnet (lntb)",
+        Currency::Regtest => "regtest (lnbcrt)",
+        Currency::Simnet => "simnet (lnsb)",
+        Currency::Signet => "signet (lntbs)",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::hashes::{Hash, sha256};
+    use bitcoin::secp256k1::{Secp256k1, SecretKey};
+    use lightning_invoice::{InvoiceBuilder, PaymentSecret};
+    use std::time::{Duration, SystemTime};
+
+    fn build_invoice(
+        currency: Currency,
+        amount_msats: Option<u64>,
+        timestamp: SystemTime,
+    ) -> String {
+        let secp = Secp256k1::new();
+        let key = SecretKey::from_slice(&[42; 32]).unwrap();
+        let mut builder = InvoiceBuilder::new(currency)
+            .description("coffee".to_string())
+            .payment_hash(sha256::Hash::from_byte_array([1; 32]))
+            .payment_secret(PaymentSecret([2; 32]))
+            .timestamp(timestamp)
+            .expiry_time(Duration::from_secs(3600))
+            .min_final_cltv_expiry_delta(144);
+        if let Some(amount_msats) = amount_msats {
+            builder = builder.amount_milli_satoshis(amount_msats);
+        }
+        builder
+            .build_signed(|hash| secp.sign_ecdsa_recoverable(hash, &key))
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_decode_signet_invoice() {
+        let invoice = build_invoice(Currency::Signet, Some(21_000_000), SystemTime::now());
+        let decoded = decode_invoice(&invoice).unwrap();
+        assert_eq!(decoded.amount_sats, 21_000);
+        assert_eq!(decoded.description, "coffee");
+        assert_eq!(decoded.payment_hash, "01".repeat(32));
+
+        assert_eq!(decoded.invoice, invoice);
+
+        let prefixed = format!(" lightning:{invoice}\n");
+        assert_eq!(decode_invoice(&prefixed).unwrap().invoice, invoice);
+    }
+
+    #[test]
+    fn test_reject_other_networks() {
+        for currency in [
+            Currency::Bitcoin,
+            Currency::BitcoinTestnet,
+            Currency::Regtest,
+        ] {
+            let invoice = build_invoice(currency, Some(1_000), SystemTime::now());
+            let err = decode_invoice(&invoice).err().unwrap();
+            assert!(err.contains("only mutinynet"), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_reject_expired() {
+        let timestamp = SystemTime::now() - Duration::from_secs(7200);
+        let invoice = build_invoice(Currency::Signet, Some(1_000), timestamp);
+        let err = decode_invoice(&invoice).err().unwrap();
+        assert!(err.contains("expired"), "{err}");
+    }
+
+    #[test]
+    fn test_amount_rounds_up() {
+        let invoice = build_invoice(Currency::Signet, Some(10_000_001), SystemTime::now());
+        assert_eq!(decode_invoice(&invoice).unwrap().amount_sats, 10_001);
+    }
+
+    #[test]
+    fn test_reject_amountless_and_garbage() {
+        let invoice = build_invoice(Currency::Signet, None, SystemTime::now());
+        assert!(decode_invoice(&invoice).is_err());
+        assert!(decode_invoice("lntbs1notaninvoice").is_err());
+    }
+}
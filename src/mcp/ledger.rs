@@ -0,0 +1,213 @@
+use crate::mcp::policy::PayoutKind;
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// serializes appends from concurrent payouts
+static LEDGER_LOCK: Mutex<()> = Mutex::new(());
+
+/// A payout the faucet made, one line of the ledger
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Payout {
+    pub timestamp: DateTime<Utc>,
+    pub kind: PayoutKind,
+    /// address or invoice that was paid
+    pub destination: String,
+    pub sats: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub txid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payment_hash: Option<String>,
+    /// key the client sent so retries don't pay twice
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+    /// sats given to the node out of a channel's capacity
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub push_amount: Option<u64>,
+}
+
+/// Which payouts to return from the ledger, every field is optional
+#[derive(Debug, Default)]
+pub struct PayoutFilter {
+    pub kind: Option<PayoutKind>,
+    /// matched case-insensitively, addresses may be given in upper case
+    pub destination: Option<String>,
+    /// inclusive
+    pub since: Option<DateTime<Utc>>,
+    /// exclusive
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl PayoutFilter {
+    pub fn matches(&self, payout: &Payout) -> bool {
+        if let Some(kind) = self.kind
+            && payout.kind != kind
+        {
+            return false;
+        }
+        if let Some(destination) = &self.destination
+            && !payout.destination.eq_ignore_ascii_case(destination.trim())
+        {
+            return false;
+        }
+        if let Some(since) = self.since
+            && payout.timestamp < since
+        {
+            return false;
+        }
+        if let Some(until) = self.until
+            && payout.timestamp >= until
+        {
+            return false;
+        }
+        true
+    }
+}
+
+/// parse a `YYYY-MM-DD` date or RFC 3339 timestamp, a date used as the end of a
+/// range covers that whole day
+pub fn parse_date_bound(value: &str, end: bool) -> Result<DateTime<Utc>, String> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+        format!("Invalid date {value}, expected YYYY-MM-DD or an RFC 3339 timestamp")
+    })?;
+    let date = if end {
+        date.checked_add_days(Days::new(1)).unwrap_or(date)
+    } else {
+        date
+    };
+    Ok(date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+}
+
+pub fn get_ledger_location() -> PathBuf {
+    home::home_dir()
+        .expect("No Home Directory found")
+        .join(".config/mcp/mutinynet/payouts.jsonl")
+}
+
+/// add a payout to the end of the ledger
+pub fn record_payout(payout: &Payout) -> std::io::Result<()> {
+    append_to(&get_ledger_location(), payout)
+}
+
+/// every payout in the ledger, oldest first
+pub fn read_payouts() -> std::io::Result<Vec<Payout>> {
+    read_from(&get_ledger_location())
+}
+
+fn append_to(path: &Path, payout: &Payout) -> std::io::Result<()> {
+    let _guard = LEDGER_LOCK.lock().unwrap();
+    std::fs::create_dir_all(path.parent().unwrap())?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    let line = format!("{}\n", serde_json::to_string(payout).unwrap());
+    file.write_all(line.as_bytes())
+}
+
+/// every payout in the ledger at `path`, oldest first
+pub fn read_from(path: &Path) -> std::io::Result<Vec<Payout>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let file = std::fs::File::open(path)?;
+    let mut payouts = vec![];
+    for line in BufReader::new(file).lines() {
+        // skip lines we can't parse, e.g. one cut short by a crash
+        if let Ok(payout) = serde_json::from_str(&line?) {
+            payouts.push(payout);
+        }
+    }
+    Ok(payouts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payout(timestamp: &str, kind: PayoutKind, destination: &str) -> Payout {
+        Payout {
+            timestamp: parse_date_bound(timestamp, false).unwrap(),
+            kind,
+            destination: destination.to_string(),
+            sats: 5_000,
+            txid: None,
+            payment_hash: None,
+            idempotency_key: None,
+            push_amount: None,
+        }
+    }
+
+    #[test]
+    fn test_append_and_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested/payouts.jsonl");
+        assert!(read_from(&path).unwrap().is_empty());
+
+        let first = payout("2025-01-01T12:00:00Z", PayoutKind::OnChain, "tb1qfirst");
+        let second = payout(
+            "2025-01-02T12:00:00Z",
+            PayoutKind::Lightning,
+            "lntbs1second",
+        );
+        append_to(&path, &first).unwrap();
+        append_to(&path, &second).unwrap();
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{\"truncated\n")
+            .unwrap();
+        assert_eq!(read_from(&path).unwrap(), vec![first, second]);
+    }
+
+    #[test]
+    fn test_filter() {
+        let payout = payout("2025-01-02T12:00:00Z", PayoutKind::OnChain, "tb1qaddress");
+        assert!(PayoutFilter::default().matches(&payout));
+
+        let filter = PayoutFilter {
+            kind: Some(PayoutKind::OnChain),
+            destination: Some("TB1QADDRESS".to_string()),
+            since: Some(parse_date_bound("2025-01-02", false).unwrap()),
+            until: Some(parse_date_bound("2025-01-02", true).unwrap()),
+        };
+        assert!(filter.matches(&payout));
+
+        let filter = PayoutFilter {
+            kind: Some(PayoutKind::Lightning),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&payout));
+        let filter = PayoutFilter {
+            since: Some(parse_date_bound("2025-01-03", false).unwrap()),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&payout));
+        let filter = PayoutFilter {
+            until: Some(parse_date_bound("2025-01-02T12:00:00Z", true).unwrap()),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&payout));
+    }
+
+    #[test]
+    fn test_parse_date_bound() {
+        assert_eq!(
+            parse_date_bound("2025-01-02", true).unwrap().to_rfc3339(),
+            "2025-01-03T00:00:00+00:00"
+        );
+        assert_eq!(
+            parse_date_bound("2025-01-02T10:00:00+02:00", false)
+                .unwrap()
+                .to_rfc3339(),
+            "2025-01-02T08:00:00+00:00"
+        );
+        assert!(parse_date_bound("yesterday", false).is_err());
+    }
+}
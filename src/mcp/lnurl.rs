@@ -0,0 +1,236 @@
+use crate::mcp::invoice::{DecodedInvoice, decode_invoice};
+use bitcoin::bech32;
+use bitcoin::hashes::{Hash, sha256};
+use lightning_invoice::{Bolt11Invoice, Bolt11InvoiceDescriptionRef};
+use reqwest::Client;
+use rpc_router::RpcResource;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::str::FromStr;
+use url::{Host, Url};
+
+/// Client for LNURL-pay servers, used to turn lightning addresses and LNURLs into invoices
+#[derive(Clone, Default, RpcResource)]
+pub struct LnurlClient {
+    client: Client,
+}
+
+/// A lightning address or LNURL to pay
+pub struct LnurlTarget {
+    /// the lightning address or LNURL, lower case
+    pub name: String,
+    /// where the pay request is fetched from
+    url: Url,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PayRequest {
+    tag: String,
+    callback: Url,
+    /// in msats
+    min_sendable: u64,
+    /// in msats
+    max_sendable: u64,
+    /// json the invoice's description hash commits to
+    metadata: String,
+}
+
+#[derive(Deserialize)]
+struct InvoiceResponse {
+    pr: String,
+}
+
+/// parse a lightning address or bech32 LNURL, `None` if the value is neither
+pub fn parse_target(value: &str) -> Option<Result<LnurlTarget, String>> {
+    let value = value.trim();
+    let value = value
+        .strip_prefix("lightning:")
+        .or_else(|| value.strip_prefix("LIGHTNING:"))
+        .unwrap_or(value)
+        .to_lowercase();
+    if value.starts_with("lnurl1") {
+        Some(parse_lnurl(value))
+    } else if value.contains('@') {
+        Some(parse_lightning_address(value))
+    } else {
+        None
+    }
+}
+
+fn parse_lnurl(lnurl: String) -> Result<LnurlTarget, String> {
+    let (_, data) = bech32::decode(&lnurl).map_err(|e| format!("Invalid LNURL: {e}"))?;
+    let url = String::from_utf8(data)
+        .ok()
+        .and_then(|url| Url::parse(&url).ok())
+        .ok_or_else(|| "Invalid LNURL, it doesn't contain a url".to_string())?;
+    if !is_secure(&url) {
+        return Err(format!("LNURL {lnurl} doesn't use https"));
+    }
+    Ok(LnurlTarget { name: lnurl, url })
+}
+
+fn parse_lightning_address(address: String) -> Result<LnurlTarget, String> {
+    let invalid = || format!("Invalid lightning address {address}");
+    let (user, domain) = address.split_once('@').ok_or_else(invalid)?;
+    if user.is_empty()
+        || !user
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.+".contains(c))
+    {
+        return Err(invalid());
+    }
+    let path = format!("/.well-known/lnurlp/{user}");
+    let mut url = Url::parse(&format!("https://{domain}{path}")).map_err(|_| invalid())?;
+    // anything after the domain would change the path
+    if url.path() != path || url.query().is_some() || url.fragment().is_some() {
+        return Err(invalid());
+    }
+    if is_local(&url) {
+        url.set_scheme("http").unwrap();
+    }
+    Ok(LnurlTarget { name: address, url })
+}
+
+/// LNURL servers must use https, unless they are on a tor or local address
+fn is_secure(url: &Url) -> bool {
+    url.scheme() == "https" || (url.scheme() == "http" && is_local(url))
+}
+
+fn is_local(url: &Url) -> bool {
+    match url.host() {
+        Some(Host::Domain(domain)) => domain == "localhost" || domain.ends_with(".onion"),
+        Some(Host::Ipv4(ip)) => ip.is_loopback(),
+        Some(Host::Ipv6(ip)) => ip.is_loopback(),
+        None => false,
+    }
+}
+
+impl LnurlClient {
+    /// ask the LNURL-pay server for an invoice of `sats`, checking it is for the amount
+    /// asked for and commits to the server's metadata
+    pub async fn fetch_invoice(
+        &self,
+        target: &LnurlTarget,
+        sats: u64,
+    ) -> Result<DecodedInvoice, String> {
+        let name = &target.name;
+        let pay: PayRequest = self.get_json(target, target.url.clone()).await?;
+        if pay.tag != "payRequest" {
+            return Err(format!("{name} is not an LNURL-pay endpoint"));
+        }
+        let msats = sats.saturating_mul(1_000);
+        if msats < pay.min_sendable || msats > pay.max_sendable {
+            return Err(format!(
+                "{name} accepts between {} and {} sats, not {sats} sats",
+                pay.min_sendable.div_ceil(1_000),
+                pay.max_sendable / 1_000
+            ));
+        }
+
+        let mut callback = pay.callback;
+        if !is_secure(&callback) {
+            return Err(format!("{name} returned a callback that doesn't use https"));
+        }
+        callback
+            .query_pairs_mut()
+            .append_pair("amount", &msats.to_string());
+        let response: InvoiceResponse = self.get_json(target, callback).await?;
+        let invoice = Bolt11Invoice::from_str(&response.pr)
+            .map_err(|e| format!("{name} returned an invalid invoice: {e}"))?;
+        if invoice.amount_milli_satoshis() != Some(msats) {
+            return Err(format!(
+                "{name} returned an invoice for a different amount than {sats} sats"
+            ));
+        }
+        let metadata_hash = sha256::Hash::hash(pay.metadata.as_bytes());
+        if !matches!(invoice.description(), Bolt11InvoiceDescriptionRef::Hash(hash) if hash.0 == metadata_hash)
+        {
+            return Err(format!(
+                "{name} returned an invoice whose description hash doesn't match its metadata"
+            ));
+        }
+        decode_invoice(&response.pr)
+    }
+
+    /// get a json response, turning LNURL error responses into their reason
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        target: &LnurlTarget,
+        url: Url,
+    ) -> Result<T, String> {
+        let name = &target.name;
+        let resp = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|_| format!("Error making request to {name}"))?;
+        let value: Value = resp
+            .json()
+            .await
+            .map_err(|_| format!("Error decoding the response from {name}"))?;
+        if value["status"] == "ERROR" {
+            let reason = value["reason"].as_str().unwrap_or("no reason given");
+            return Err(format!("{name} returned an error: {reason}"));
+        }
+        serde_json::from_value(value)
+            .map_err(|_| format!("Error decoding the response from {name}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::bech32::{Bech32, Hrp};
+
+    fn parse(value: &str) -> Result<LnurlTarget, String> {
+        parse_target(value).expect("not an lnurl")
+    }
+
+    #[test]
+    fn test_lightning_address() {
+        let target = parse(" Alice@Example.com ").unwrap();
+        assert_eq!(target.name, "alice@example.com");
+        assert_eq!(
+            target.url.as_str(),
+            "https://example.com/.well-known/lnurlp/alice"
+        );
+
+        let target = parse("lightning:bob@127.0.0.1:8080").unwrap();
+        assert_eq!(
+            target.url.as_str(),
+            "http://127.0.0.1:8080/.well-known/lnurlp/bob"
+        );
+
+        for address in [
+            "@example.com",
+            "a b@example.com",
+            "alice@",
+            "alice@example.com/x",
+        ] {
+            assert!(parse(address).is_err(), "{address}");
+        }
+        assert!(parse_target("lntbs1invoice").is_none());
+    }
+
+    #[test]
+    fn test_lnurl() {
+        let encode = |url: &str| {
+            bech32::encode_upper::<Bech32>(Hrp::parse("lnurl").unwrap(), url.as_bytes()).unwrap()
+        };
+        let url = "https://example.com/lnurlp/alice?tag=pay";
+        let lnurl = encode(url);
+        let target = parse(&format!("lightning:{lnurl}")).unwrap();
+        assert_eq!(target.name, lnurl.to_lowercase());
+        assert_eq!(target.url.as_str(), url);
+
+        let err = parse(&encode("http://example.com/lnurlp/alice"))
+            .err()
+            .unwrap();
+        assert!(err.ends_with("doesn't use https"), "{err}");
+        assert!(parse(&encode("not a url")).is_err());
+        assert!(parse("lnurl1garbage").is_err());
+    }
+}
@@ -1,10 +1,32 @@
+pub mod address;
+pub mod bip21;
+pub mod channel;
+pub mod config;
+pub mod destination;
+pub mod elicitation;
+pub mod esplora;
+pub mod faucet;
+pub mod http;
+pub mod idempotency;
+pub mod invoice;
+pub mod ledger;
+pub mod lnurl;
+pub mod offer;
+pub mod policy;
 pub mod prompts;
 pub mod resources;
+pub mod server;
+pub mod session;
+pub mod stdio;
 pub mod tools;
+pub mod transaction;
+pub mod transcript;
 pub mod types;
 pub mod utilities;
 
 const JSONRPC_VERSION: &str = "2.0";
 const PROTOCOL_VERSION: &str = "2024-11-05";
+/// versions we can speak, elicitation needs 2025-06-18
+const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", PROTOCOL_VERSION];
 const SERVER_NAME: &str = "mcp-mutinynet";
 const SERVER_VERSION: &str = "0.1.0";
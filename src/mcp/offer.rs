@@ -0,0 +1,171 @@
+use bitcoin::Network;
+use bitcoin::constants::ChainHash;
+use lightning::offers::offer::{Amount, Offer};
+use std::str::FromStr;
+
+/// The parts of a bolt12 offer worth showing the agent before and after paying it
+pub struct DecodedOffer {
+    /// the offer without whitespace or a `lightning:` prefix
+    pub offer: String,
+    /// sats that will be paid, chosen by the caller or the offer's own amount
+    pub amount_sats: u64,
+    pub min_amount_sats: Option<u64>,
+    pub issuer: Option<String>,
+    pub description: Option<String>,
+}
+
+impl DecodedOffer {
+    /// human readable summary, one field per line
+    pub fn summary(&self) -> String {
+        let mut lines = vec![format!("Amount: {} sats", self.amount_sats)];
+        if let Some(min_amount_sats) = self.min_amount_sats {
+            lines.push(format!("Offer amount: {min_amount_sats} sats"));
+        }
+        if let Some(issuer) = &self.issuer {
+            lines.push(format!("Issuer: {issuer}"));
+        }
+        if let Some(description) = &self.description {
+            lines.push(format!("Description: {description}"));
+        }
+        lines.join("\n")
+    }
+}
+
+/// parse a bolt12 offer and work out how much to pay it, `amount` is the caller's choice
+/// in sats and can't be below the offer's own amount, spend limits are left to the policy
+pub fn decode_offer(offer: &str, amount: Option<u64>) -> Result<DecodedOffer, String> {
+    let offer = offer.trim();
+    let offer = offer
+        .strip_prefix("lightning:")
+        .or_else(|| offer.strip_prefix("LIGHTNING:"))
+        .unwrap_or(offer);
+    let offer = Offer::from_str(offer).map_err(|e| format!("Invalid bolt12 offer: {e:?}"))?;
+
+    if !offer.supports_chain(ChainHash::using_genesis_block(Network::Signet)) {
+        return Err(
+            "Offer is for another network, only mutinynet (signet) offers can be paid".to_string(),
+        );
+    }
+    if offer.is_expired() {
+        return Err("Offer has expired, ask for a new one".to_string());
+    }
+
+    let min_amount_sats = match offer.amount() {
+        None => None,
+        Some(Amount::Bitcoin { amount_msats }) => Some(amount_msats.div_ceil(1_000)),
+        Some(Amount::Currency { iso4217_code, .. }) => {
+            return Err(format!(
+                "Offer is priced in {}, only offers priced in bitcoin can be paid",
+                String::from_utf8_lossy(&iso4217_code)
+            ));
+        }
+    };
+    let amount_sats = match (amount, min_amount_sats) {
+        (Some(amount), Some(min)) if amount < min => {
+            return Err(format!(
+                "Amount of {amount} sats is below the offer's amount of {min} sats"
+            ));
+        }
+        (Some(0), None) => return Err("Amount must be at least 1 sat".to_string()),
+        (Some(amount), _) => amount,
+        (None, Some(min)) => min,
+        (None, None) => {
+            return Err("Offer has no amount, pass the amount to pay in sats".to_string());
+        }
+    };
+
+    Ok(DecodedOffer {
+        offer: offer.to_string(),
+        amount_sats,
+        min_amount_sats,
+        issuer: offer.issuer().map(|issuer| issuer.to_string()),
+        description: offer
+            .description()
+            .map(|description| description.to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::secp256k1::{PublicKey, Secp256k1, SecretKey, SignOnly};
+    use lightning::offers::offer::{ExplicitMetadata, OfferBuilder};
+    use std::time::{Duration, SystemTime};
+
+    fn build_offer(
+        network: Network,
+        amount_msats: Option<u64>,
+    ) -> OfferBuilder<'static, ExplicitMetadata, SignOnly> {
+        let secp = Secp256k1::new();
+        let key = SecretKey::from_slice(&[42; 32]).unwrap();
+        let mut builder = OfferBuilder::new(PublicKey::from_secret_key(&secp, &key))
+            .chain(network)
+            .description("coffee".to_string())
+            .issuer("Mutiny".to_string());
+        if let Some(amount_msats) = amount_msats {
+            builder = builder.amount_msats(amount_msats);
+        }
+        builder
+    }
+
+    #[test]
+    fn test_decode_offer() {
+        let offer = build_offer(Network::Signet, Some(1_500)).build().unwrap();
+        let decoded = decode_offer(&format!(" lightning:{offer} "), None).unwrap();
+        assert_eq!(decoded.offer, offer.to_string());
+        assert_eq!(decoded.amount_sats, 2);
+        assert_eq!(
+            decoded.summary(),
+            "Amount: 2 sats\nOffer amount: 2 sats\nIssuer: Mutiny\nDescription: coffee"
+        );
+
+        let decoded = decode_offer(&offer.to_string(), Some(5_000)).unwrap();
+        assert_eq!(decoded.amount_sats, 5_000);
+        let err = decode_offer(&offer.to_string(), Some(1)).err().unwrap();
+        assert_eq!(
+            err,
+            "Amount of 1 sats is below the offer's amount of 2 sats"
+        );
+    }
+
+    #[test]
+    fn test_offer_without_amount() {
+        let offer = build_offer(Network::Signet, None)
+            .build()
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            decode_offer(&offer, Some(5_000)).unwrap().amount_sats,
+            5_000
+        );
+        let err = decode_offer(&offer, None).err().unwrap();
+        assert_eq!(err, "Offer has no amount, pass the amount to pay in sats");
+        assert!(decode_offer(&offer, Some(0)).is_err());
+    }
+
+    #[test]
+    fn test_invalid_offers() {
+        let mainnet = build_offer(Network::Bitcoin, None)
+            .build()
+            .unwrap()
+            .to_string();
+        let err = decode_offer(&mainnet, Some(1_000)).err().unwrap();
+        assert!(err.contains("only mutinynet"), "{err}");
+
+        let expired = build_offer(Network::Signet, None)
+            .absolute_expiry(
+                SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap()
+                    - Duration::from_secs(60),
+            )
+            .build()
+            .unwrap()
+            .to_string();
+        let err = decode_offer(&expired, Some(1_000)).err().unwrap();
+        assert!(err.contains("expired"), "{err}");
+
+        let err = decode_offer("lno1garbage", Some(1_000)).err().unwrap();
+        assert!(err.starts_with("Invalid bolt12 offer"), "{err}");
+    }
+}
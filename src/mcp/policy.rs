@@ -0,0 +1,430 @@
+use crate::mcp::elicitation;
+use crate::mcp::ledger;
+use crate::mcp::session::Session;
+use rpc_router::RpcResource;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// per-call cap used for payouts when the config doesn't set one
+pub const DEFAULT_MAX_PER_CALL: u64 = 1_000_000;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PayoutKind {
+    OnChain,
+    Lightning,
+    /// capacity of a channel opened by the faucet, push amount included
+    Channel,
+}
+
+impl PayoutKind {
+    fn name(self) -> &'static str {
+        match self {
+            PayoutKind::OnChain => "on-chain",
+            PayoutKind::Lightning => "lightning",
+            PayoutKind::Channel => "channel",
+        }
+    }
+}
+
+/// Caps on the sats paid out for one kind of payout, `None` means unlimited
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+    pub per_call: Option<u64>,
+    pub per_session: Option<u64>,
+    /// resets at midnight UTC
+    pub per_day: Option<u64>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            per_call: Some(DEFAULT_MAX_PER_CALL),
+            per_session: None,
+            per_day: None,
+        }
+    }
+}
+
+/// The `[limits]` table of the config file
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct LimitsConfig {
+    pub onchain: Limits,
+    pub lightning: Limits,
+    pub channel: Limits,
+}
+
+impl LimitsConfig {
+    fn get(&self, kind: PayoutKind) -> Limits {
+        match kind {
+            PayoutKind::OnChain => self.onchain,
+            PayoutKind::Lightning => self.lightning,
+            PayoutKind::Channel => self.channel,
+        }
+    }
+}
+
+/// Sats paid out so far, split by kind
+#[derive(Debug, Default)]
+pub struct PayoutTotals {
+    onchain: u64,
+    lightning: u64,
+    channel: u64,
+}
+
+impl PayoutTotals {
+    fn get_mut(&mut self, kind: PayoutKind) -> &mut u64 {
+        match kind {
+            PayoutKind::OnChain => &mut self.onchain,
+            PayoutKind::Lightning => &mut self.lightning,
+            PayoutKind::Channel => &mut self.channel,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct DailyTotals {
+    /// days since the unix epoch these totals are for
+    day: u64,
+    totals: PayoutTotals,
+}
+
+/// Enforces the configured spend limits, shared by every session of the process
+#[derive(Clone, RpcResource)]
+pub struct Policy {
+    limits: LimitsConfig,
+    /// ask the user through elicitation before every payout
+    confirm_payments: bool,
+    /// how long to wait for the user's answer before refusing the payout
+    confirm_timeout: Duration,
+    /// never ask the faucet to pay, whatever the tool arguments say
+    dry_run: bool,
+    daily: Arc<Mutex<DailyTotals>>,
+    /// payouts recorded here count towards the daily totals
+    ledger_path: PathBuf,
+}
+
+/// Sats counted towards the session and daily totals for a payout that is being made, given
+/// back when dropped unless `commit` is called once the faucet has paid, so a payout that fails,
+/// is cancelled or panics doesn't use up the limits
+#[must_use]
+pub struct Reservation {
+    policy: Policy,
+    session: Session,
+    kind: PayoutKind,
+    sats: u64,
+    committed: bool,
+}
+
+impl Reservation {
+    /// keep the sats counted, the payout was made
+    pub fn commit(mut self) {
+        self.committed = true;
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        if !self.committed {
+            self.policy.release(&self.session, self.kind, self.sats);
+        }
+    }
+}
+
+impl Policy {
+    pub fn new(
+        limits: LimitsConfig,
+        confirm_payments: bool,
+        confirm_timeout: Duration,
+        dry_run: bool,
+    ) -> Self {
+        Policy {
+            limits,
+            confirm_payments,
+            confirm_timeout,
+            dry_run,
+            daily: Arc::new(Mutex::new(DailyTotals::default())),
+            ledger_path: ledger::get_ledger_location(),
+        }
+    }
+
+    /// whether a payout should only be described, `requested` is the tool's `dry_run` argument
+    pub fn is_dry_run(&self, requested: Option<bool>) -> bool {
+        self.dry_run || requested.unwrap_or(false)
+    }
+
+    /// get the user's approval for a payout if confirmation is enabled,
+    /// the error explains why the payout must not be made
+    pub async fn confirm(&self, session: &Session, message: String) -> Result<(), String> {
+        if !self.confirm_payments {
+            return Ok(());
+        }
+        elicitation::confirm(session, message, self.confirm_timeout).await
+    }
+
+    /// check a payout against every cap and count it towards the session and daily totals,
+    /// the error explains which cap it would break
+    pub fn reserve(
+        &self,
+        session: &Session,
+        kind: PayoutKind,
+        sats: u64,
+    ) -> Result<Reservation, String> {
+        self.reserve_at(session, kind, sats, SystemTime::now())?;
+        Ok(Reservation {
+            policy: self.clone(),
+            session: session.clone(),
+            kind,
+            sats,
+            committed: false,
+        })
+    }
+
+    /// give back a reservation for a payout the faucet didn't make
+    fn release(&self, session: &Session, kind: PayoutKind, sats: u64) {
+        let mut daily = self.daily.lock().unwrap();
+        let spent = daily.totals.get_mut(kind);
+        *spent = spent.saturating_sub(sats);
+        let mut session_totals = session.payout_totals();
+        let spent = session_totals.get_mut(kind);
+        *spent = spent.saturating_sub(sats);
+    }
+
+    /// totals of the payouts already recorded in the ledger for the given day, so a restart
+    /// doesn't reset the daily limits
+    fn recorded_totals(&self, day: u64) -> PayoutTotals {
+        let mut totals = PayoutTotals::default();
+        // a ledger we can't read is reported when listing payouts, don't block payouts over it
+        for payout in ledger::read_from(&self.ledger_path).unwrap_or_default() {
+            if payout.timestamp.timestamp().max(0) as u64 / SECONDS_PER_DAY == day {
+                let spent = totals.get_mut(payout.kind);
+                *spent = spent.saturating_add(payout.sats);
+            }
+        }
+        totals
+    }
+
+    fn reserve_at(
+        &self,
+        session: &Session,
+        kind: PayoutKind,
+        sats: u64,
+        now: SystemTime,
+    ) -> Result<(), String> {
+        let limits = self.limits.get(kind);
+        let name = kind.name();
+        if let Some(limit) = limits.per_call
+            && sats > limit
+        {
+            return Err(format!(
+                "Payout of {sats} sats is above the {name} limit of {limit} sats per payout"
+            ));
+        }
+
+        // always lock the daily totals first so concurrent payouts can't deadlock
+        let mut daily = self.daily.lock().unwrap();
+        let today = now
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            / SECONDS_PER_DAY;
+        if daily.day != today {
+            *daily = DailyTotals {
+                day: today,
+                totals: self.recorded_totals(today),
+            };
+        }
+        let mut session_totals = session.payout_totals();
+
+        let session_spent = *session_totals.get_mut(kind);
+        if let Some(limit) = limits.per_session
+            && session_spent
+                .checked_add(sats)
+                .is_none_or(|total| total > limit)
+        {
+            return Err(format!(
+                "Payout of {sats} sats is above the {name} limit of {limit} sats per session, {} sats remaining",
+                limit.saturating_sub(session_spent)
+            ));
+        }
+        let daily_spent = *daily.totals.get_mut(kind);
+        if let Some(limit) = limits.per_day
+            && daily_spent
+                .checked_add(sats)
+                .is_none_or(|total| total > limit)
+        {
+            return Err(format!(
+                "Payout of {sats} sats is above the {name} limit of {limit} sats per day, {} sats remaining today",
+                limit.saturating_sub(daily_spent)
+            ));
+        }
+
+        // unlimited totals can't overflow either, they just stop counting
+        let spent = session_totals.get_mut(kind);
+        *spent = spent.saturating_add(sats);
+        let spent = daily.totals.get_mut(kind);
+        *spent = spent.saturating_add(sats);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::ledger::Payout;
+    use chrono::DateTime;
+
+    fn policy(onchain: Limits) -> Policy {
+        Policy {
+            ledger_path: PathBuf::from("/nonexistent/payouts.jsonl"),
+            ..Policy::new(
+                LimitsConfig {
+                    onchain,
+                    ..LimitsConfig::default()
+                },
+                false,
+                Duration::from_secs(60),
+                false,
+            )
+        }
+    }
+
+    #[test]
+    fn test_per_call_limit() {
+        let policy = policy(Limits::default());
+        let (session, _rx) = Session::new();
+        let err = policy
+            .reserve(&session, PayoutKind::OnChain, DEFAULT_MAX_PER_CALL + 1)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err,
+            "Payout of 1000001 sats is above the on-chain limit of 1000000 sats per payout"
+        );
+        assert!(
+            policy
+                .reserve(&session, PayoutKind::OnChain, DEFAULT_MAX_PER_CALL)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_per_session_limit() {
+        let policy = policy(Limits {
+            per_call: None,
+            per_session: Some(10_000),
+            per_day: None,
+        });
+        let (session, _rx) = Session::new();
+        let first = policy
+            .reserve(&session, PayoutKind::OnChain, 6_000)
+            .unwrap();
+        let err = policy
+            .reserve(&session, PayoutKind::OnChain, 6_000)
+            .err()
+            .unwrap();
+        assert!(err.contains("per session, 4000 sats remaining"), "{err}");
+
+        // lightning is counted separately, as is every other session
+        let _lightning = policy
+            .reserve(&session, PayoutKind::Lightning, 6_000)
+            .unwrap();
+        let (other, _rx) = Session::new();
+        let _other = policy.reserve(&other, PayoutKind::OnChain, 6_000).unwrap();
+
+        // failed payouts don't count
+        drop(first);
+        policy
+            .reserve(&session, PayoutKind::OnChain, 6_000)
+            .unwrap()
+            .commit();
+        // payouts that were made do
+        assert!(
+            policy
+                .reserve(&session, PayoutKind::OnChain, 6_000)
+                .is_err()
+        );
+        assert!(
+            policy
+                .reserve(&session, PayoutKind::OnChain, u64::MAX)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_per_day_limit_resets() {
+        let policy = policy(Limits {
+            per_call: None,
+            per_session: None,
+            per_day: Some(10_000),
+        });
+        let (session, _rx) = Session::new();
+        let (other, _rx) = Session::new();
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(20_000 * SECONDS_PER_DAY);
+        policy
+            .reserve_at(&session, PayoutKind::OnChain, 6_000, now)
+            .unwrap();
+        let err = policy
+            .reserve_at(&other, PayoutKind::OnChain, 6_000, now)
+            .unwrap_err();
+        assert!(err.contains("per day, 4000 sats remaining today"), "{err}");
+
+        let tomorrow = now + Duration::from_secs(SECONDS_PER_DAY);
+        policy
+            .reserve_at(&other, PayoutKind::OnChain, 6_000, tomorrow)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_per_day_limit_counts_ledger() {
+        let dir = tempfile::tempdir().unwrap();
+        let ledger_path = dir.path().join("payouts.jsonl");
+        let day = 20_000;
+        for (days_ago, kind) in [
+            (0, PayoutKind::OnChain),
+            (0, PayoutKind::Lightning),
+            (1, PayoutKind::OnChain),
+        ] {
+            let secs = (day - days_ago) * SECONDS_PER_DAY + 60;
+            let payout = Payout {
+                timestamp: DateTime::from_timestamp(secs as i64, 0).unwrap(),
+                kind,
+                destination: "tb1qaddress".to_string(),
+                sats: 4_000,
+                txid: None,
+                payment_hash: None,
+                idempotency_key: None,
+                push_amount: None,
+            };
+            let line = format!("{}\n", serde_json::to_string(&payout).unwrap());
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&ledger_path)
+                .unwrap();
+            std::io::Write::write_all(&mut file, line.as_bytes()).unwrap();
+        }
+        let policy = Policy {
+            ledger_path,
+            ..policy(Limits {
+                per_call: None,
+                per_session: None,
+                per_day: Some(10_000),
+            })
+        };
+
+        // only today's on-chain payout from before the restart counts
+        let (session, _rx) = Session::new();
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(day * SECONDS_PER_DAY + 3_600);
+        let err = policy
+            .reserve_at(&session, PayoutKind::OnChain, 7_000, now)
+            .unwrap_err();
+        assert!(err.contains("per day, 6000 sats remaining today"), "{err}");
+    }
+}
@@ -1,38 +1,104 @@
+use crate::mcp::esplora::EsploraClient;
+use crate::mcp::ledger::read_payouts;
 use crate::mcp::types::*;
 use crate::mcp::utilities::{get_bearer_token, get_bearer_token_location};
-use rpc_router::HandlerResult;
+use rpc_router::{HandlerResult, IntoHandlerError};
+use serde_json::json;
 use url::Url;
 
+/// every payout the faucet made for us, as a json array
+pub const HISTORY_URI: &str = "mutinynet://history";
+/// the latest mutinynet block and the mempool, as json
+pub const CHAIN_TIP_URI: &str = "mutinynet://chain/tip";
+
+fn bearer_token_uri() -> Url {
+    Url::parse(&format!(
+        "file://{}",
+        get_bearer_token_location().to_str().unwrap()
+    ))
+    .unwrap()
+}
+
 pub async fn resources_list(
     _request: Option<ListResourcesRequest>,
 ) -> HandlerResult<ListResourcesResult> {
     let response = ListResourcesResult {
-        resources: vec![Resource {
-            uri: Url::parse(&format!(
-                "file://{}",
-                get_bearer_token_location().to_str().unwrap()
-            ))
-            .unwrap(),
-            name: "github bearer token available".to_string(),
-            description: Some(
-                "The github bearer token will be available if the user is logged in".to_string(),
-            ),
-            mime_type: Some("text/plain".to_string()),
-        }],
+        resources: vec![
+            Resource {
+                uri: bearer_token_uri(),
+                name: "github bearer token available".to_string(),
+                description: Some(
+                    "The github bearer token will be available if the user is logged in"
+                        .to_string(),
+                ),
+                mime_type: Some("text/plain".to_string()),
+            },
+            Resource {
+                uri: Url::parse(HISTORY_URI).unwrap(),
+                name: "payout history".to_string(),
+                description: Some(
+                    "Every payout made by the faucet through this server, oldest first".to_string(),
+                ),
+                mime_type: Some("application/json".to_string()),
+            },
+            Resource {
+                uri: Url::parse(CHAIN_TIP_URI).unwrap(),
+                name: "chain tip".to_string(),
+                description: Some(
+                    "Height, hash and time of the latest mutinynet block and the size of the mempool"
+                        .to_string(),
+                ),
+                mime_type: Some("application/json".to_string()),
+            },
+        ],
         next_cursor: None,
     };
     Ok(response)
 }
 
-pub async fn resource_read(request: ReadResourceRequest) -> HandlerResult<ReadResourceResult> {
-    let has_token = get_bearer_token().is_some();
-    let response = ReadResourceResult {
-        content: ResourceContent {
+pub async fn resource_read(
+    esplora: EsploraClient,
+    request: ReadResourceRequest,
+) -> HandlerResult<ReadResourceResult> {
+    let content = if request.uri.as_str() == HISTORY_URI {
+        let payouts = read_payouts().map_err(|e| {
+            json!({"code": -32603, "message": format!("Failed to read payout history: {e}")})
+                .into_handler_error()
+        })?;
+        ResourceContent {
+            uri: request.uri.clone(),
+            mime_type: Some("application/json".to_string()),
+            text: Some(serde_json::to_string_pretty(&payouts).unwrap()),
+            blob: None,
+        }
+    } else if request.uri.as_str() == CHAIN_TIP_URI {
+        let tip = esplora.get_chain_tip().await.map_err(|e| {
+            json!({"code": -32603, "message": format!("Failed to look up the chain tip: {e}")})
+                .into_handler_error()
+        })?;
+        ResourceContent {
+            uri: request.uri.clone(),
+            mime_type: Some("application/json".to_string()),
+            text: Some(serde_json::to_string_pretty(&tip).unwrap()),
+            blob: None,
+        }
+    } else if request.uri == bearer_token_uri() {
+        let has_token = get_bearer_token().is_some();
+        ResourceContent {
             uri: request.uri.clone(),
             mime_type: Some("text/plain".to_string()),
             text: Some(has_token.to_string()),
             blob: None,
-        },
+        }
+    } else {
+        return Err(json!({
+            "code": -32002,
+            "message": "Resource not found",
+            "data": { "uri": request.uri },
+        })
+        .into_handler_error());
     };
-    Ok(response)
+    Ok(ReadResourceResult {
+        contents: vec![content],
+    })
 }
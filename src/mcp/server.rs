@@ -0,0 +1,358 @@
+use crate::mcp::session::{ProgressReporter, Session};
+use crate::mcp::types::{
+    CancelledNotification, ErrorCode, JsonRpcError, JsonRpcResponse, LoggingLevel,
+    ToolCallRequestParams,
+};
+use crate::mcp::utilities::{notifications_cancelled, notifications_initialized};
+use rpc_router::{Error, Request, Resources, Router};
+use serde_json::{Value, json};
+
+/// handle a single json-rpc message from the client, returning the response if one is expected
+pub async fn handle_message(
+    router: &Router,
+    session: &Session,
+    json_value: Value,
+) -> Option<Value> {
+    accept_message(session, &json_value);
+    process_message(router, session, json_value).await
+}
+
+/// start tracking a request as soon as it is read, transports that process messages on their
+/// own tasks call this before spawning so an early `notifications/cancelled` isn't missed
+pub fn accept_message(session: &Session, json_value: &Value) {
+    if json_value.get("method").is_some()
+        && let Some(id) = json_value.get("id")
+    {
+        session.start_request(id);
+    }
+}
+
+/// handle a message that has been through `accept_message`
+pub async fn process_message(
+    router: &Router,
+    session: &Session,
+    json_value: Value,
+) -> Option<Value> {
+    // notifications, no response required
+    if json_value.is_object() && json_value.get("id").is_none() {
+        match json_value.get("method").and_then(Value::as_str) {
+            Some("notifications/initialized") => notifications_initialized(),
+            Some("notifications/cancelled") => {
+                let params_value = json_value.get("params").cloned().unwrap_or_default();
+                if let Ok(cancel_params) =
+                    serde_json::from_value::<CancelledNotification>(params_value)
+                {
+                    notifications_cancelled(session, cancel_params);
+                }
+            }
+            _ => {}
+        }
+        return None;
+    }
+
+    // response to a request we sent the client
+    if json_value.get("method").is_none() {
+        session.handle_response(&json_value);
+        return None;
+    }
+
+    // normal json-rpc message, and response expected
+    let id = json_value.get("id").cloned().unwrap_or_default();
+    let Ok(mut rpc_request) = Request::from_value(json_value) else {
+        session.complete_request(&id);
+        return None;
+    };
+    let mut progress_token = None;
+    if rpc_request.method == "tools/call" {
+        let params = rpc_request
+            .params
+            .and_then(|params| serde_json::from_value::<ToolCallRequestParams>(params).ok());
+        let Some(params) = params else {
+            session.complete_request(&id);
+            let json_error = JsonRpcError::new(
+                id,
+                ErrorCode::InvalidParams as i32,
+                "Invalid tool call params",
+            );
+            return Some(serde_json::to_value(json_error).unwrap());
+        };
+        progress_token = params.meta.and_then(|meta| meta.progress_token);
+        rpc_request = Request {
+            id: id.clone(),
+            method: params.name,
+            params: params.arguments,
+        }
+    }
+
+    // run the call on its own task so `notifications/cancelled` can abort it
+    let resources = Resources::builder()
+        .append(session.clone())
+        .append(ProgressReporter::new(session.clone(), progress_token))
+        .build();
+    let call_router = router.clone();
+    let call = tokio::spawn(async move {
+        call_router
+            .call_with_resources(rpc_request, resources)
+            .await
+    });
+    // aborts the call right away if it was cancelled while waiting to be spawned
+    session.register_request(&id, call.abort_handle());
+    let call_result = call.await;
+    session.complete_request(&id);
+    let call_result = match call_result {
+        Ok(call_result) => call_result,
+        // cancelled requests must not send a response
+        Err(e) if e.is_cancelled() => return None,
+        Err(_) => {
+            let json_error =
+                JsonRpcError::new(id, ErrorCode::InternalError as i32, "Internal error");
+            return Some(serde_json::to_value(json_error).unwrap());
+        }
+    };
+
+    match call_result {
+        Ok(call_response) => {
+            if call_response.value.is_null() {
+                return None;
+            }
+            let response = JsonRpcResponse::new(id, call_response.value);
+            Some(serde_json::to_value(response).unwrap())
+        }
+        Err(error) => match &error.error {
+            // error from json-rpc call
+            Error::Handler(handler) => {
+                let error_value = handler.get::<Value>()?;
+                session.log(
+                    LoggingLevel::Error,
+                    "server",
+                    json!({ "method": error.method, "error": error_value }),
+                );
+                Some(json!({
+                    "jsonrpc": "2.0",
+                    "error": error_value,
+                    "id": id
+                }))
+            }
+            _ => {
+                let json_error = JsonRpcError::new(id, -1, "Invalid json-rpc call");
+                Some(serde_json::to_value(json_error).unwrap())
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::utilities::logging_set_level;
+    use rpc_router::{Handler, HandlerResult, IntoHandlerError, RouterBuilder, RpcParams};
+    use serde::Deserialize;
+    use std::time::Duration;
+
+    #[derive(Deserialize, RpcParams)]
+    struct SleepRequest {
+        millis: u64,
+    }
+
+    async fn sleep(req: SleepRequest) -> HandlerResult<Value> {
+        tokio::time::sleep(Duration::from_millis(req.millis)).await;
+        Ok(json!({ "slept": req.millis }))
+    }
+
+    #[derive(Deserialize, RpcParams)]
+    struct CountRequest {
+        steps: u64,
+    }
+
+    async fn count(progress: ProgressReporter, req: CountRequest) -> HandlerResult<Value> {
+        for i in 1..=req.steps {
+            progress.report(i, Some(req.steps), format!("step {i}"));
+        }
+        Ok(json!({ "counted": req.steps }))
+    }
+
+    async fn fail(_: CountRequest) -> HandlerResult<Value> {
+        Err(json!({"code": -32603, "message": "failed"}).into_handler_error())
+    }
+
+    fn test_router() -> Router {
+        RouterBuilder::default()
+            .append_dyn("logging/setLevel", logging_set_level.into_dyn())
+            .append_dyn("fail", fail.into_dyn())
+            .append_dyn("sleep", sleep.into_dyn())
+            .append_dyn("count", count.into_dyn())
+            .build()
+    }
+
+    fn sleep_request(id: Value, millis: u64) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "tools/call",
+            "params": { "name": "sleep", "arguments": { "millis": millis } }
+        })
+    }
+
+    fn cancel_notification(id: Value) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/cancelled",
+            "params": { "requestId": id, "reason": "test" }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_completed_request_responds() {
+        let router = test_router();
+        let (session, _outgoing) = Session::new();
+
+        let response = handle_message(&router, &session, sleep_request(json!(1), 1)).await;
+        let response = response.expect("response");
+        assert_eq!(response["id"], json!(1));
+        assert_eq!(response["result"]["slept"], json!(1));
+    }
+
+    #[tokio::test]
+    async fn test_progress_notifications() {
+        let router = test_router();
+        let (session, mut outgoing) = Session::new();
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {
+                "name": "count",
+                "arguments": { "steps": 2 },
+                "_meta": { "progressToken": "abc" }
+            }
+        });
+        let response = handle_message(&router, &session, request).await;
+        assert_eq!(response.unwrap()["result"]["counted"], json!(2));
+
+        for i in 1..=2 {
+            let notification = outgoing.recv().await.unwrap();
+            assert_eq!(notification["method"], json!("notifications/progress"));
+            assert_eq!(notification["params"]["progressToken"], json!("abc"));
+            assert_eq!(notification["params"]["progress"], json!(i));
+            assert_eq!(notification["params"]["total"], json!(2));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_no_progress_without_token() {
+        let router = test_router();
+        let (session, mut outgoing) = Session::new();
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": { "name": "count", "arguments": { "steps": 2 } }
+        });
+        assert!(handle_message(&router, &session, request).await.is_some());
+        drop(session);
+        assert!(outgoing.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_errors_logged_at_selected_level() {
+        let router = test_router();
+        let (session, mut outgoing) = Session::new();
+        let fail_request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": { "name": "fail", "arguments": { "steps": 0 } }
+        });
+
+        let response = handle_message(&router, &session, fail_request.clone()).await;
+        assert_eq!(response.unwrap()["error"]["message"], json!("failed"));
+        let notification = outgoing.recv().await.unwrap();
+        assert_eq!(notification["method"], json!("notifications/message"));
+        assert_eq!(notification["params"]["level"], json!("error"));
+        assert_eq!(notification["params"]["data"]["method"], json!("fail"));
+
+        let set_level = json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "logging/setLevel",
+            "params": { "level": "critical" }
+        });
+        assert!(handle_message(&router, &session, set_level).await.is_some());
+        assert!(
+            handle_message(&router, &session, fail_request)
+                .await
+                .is_some()
+        );
+        drop(session);
+        assert!(outgoing.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_request_never_responds() {
+        let router = test_router();
+        let (session, mut outgoing) = Session::new();
+
+        let request = {
+            let router = router.clone();
+            let session = session.clone();
+            tokio::spawn(async move {
+                handle_message(&router, &session, sleep_request(json!("slow"), 60_000)).await
+            })
+        };
+        // give the request time to be registered as in-flight
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let ack = handle_message(&router, &session, cancel_notification(json!("slow"))).await;
+        assert!(ack.is_none());
+
+        let response = tokio::time::timeout(Duration::from_secs(5), request)
+            .await
+            .expect("cancelled request should finish promptly")
+            .unwrap();
+        assert!(response.is_none());
+        drop(session);
+        assert!(outgoing.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_before_request_runs() {
+        let router = test_router();
+        let (session, _outgoing) = Session::new();
+
+        // the cancellation is handled before the request's task gets to run
+        let request = sleep_request(json!(3), 60_000);
+        accept_message(&session, &request);
+        handle_message(&router, &session, cancel_notification(json!(3))).await;
+
+        let response = tokio::time::timeout(
+            Duration::from_secs(5),
+            process_message(&router, &session, request),
+        )
+        .await
+        .expect("cancelled request should finish promptly");
+        assert!(response.is_none());
+        assert!(!session.has_in_flight_requests());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_only_matches_same_id() {
+        let router = test_router();
+        let (session, _outgoing) = Session::new();
+
+        let request = {
+            let router = router.clone();
+            let session = session.clone();
+            tokio::spawn(async move {
+                handle_message(&router, &session, sleep_request(json!(7), 50)).await
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        // a string id is not the same request as a numeric one
+        handle_message(&router, &session, cancel_notification(json!("7"))).await;
+
+        let response = request.await.unwrap().expect("response");
+        assert_eq!(response["id"], json!(7));
+    }
+}
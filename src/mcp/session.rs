@@ -0,0 +1,247 @@
+use crate::mcp::JSONRPC_VERSION;
+use crate::mcp::policy::PayoutTotals;
+use crate::mcp::types::{ClientCapabilities, LoggingLevel, LoggingMessageNotification, Progress};
+use crate::mcp::utilities::notify;
+use rpc_router::RpcResource;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+use tokio::sync::oneshot;
+use tokio::task::AbortHandle;
+
+/// where the client's response to a server-initiated request is delivered
+type ResponseSender = oneshot::Sender<Result<Value, Value>>;
+
+/// level used until the client sends `logging/setLevel`
+const DEFAULT_LOG_LEVEL: LoggingLevel = LoggingLevel::Info;
+
+/// Why a request sent to the client has no result
+#[derive(Debug)]
+pub enum RequestError {
+    /// the client responded with this json-rpc error
+    Response(Value),
+    /// the client went away before responding, or the request couldn't be delivered
+    Disconnected,
+    /// the client didn't respond in time
+    TimedOut,
+}
+
+/// A connected MCP client, handed to handlers as a router resource
+#[derive(Clone, RpcResource)]
+pub struct Session {
+    outgoing: UnboundedSender<Value>,
+    /// requests currently being handled, keyed by their serialized json-rpc id, with the task
+    /// running them once it has been spawned
+    in_flight: Arc<Mutex<HashMap<String, Option<AbortHandle>>>>,
+    /// minimum level of `notifications/message` the client wants to receive
+    log_level: Arc<Mutex<LoggingLevel>>,
+    /// sats paid out to this client, checked against the per-session limits
+    payout_totals: Arc<Mutex<PayoutTotals>>,
+    /// what the client said it supports in `initialize`
+    client_capabilities: Arc<Mutex<ClientCapabilities>>,
+    /// id for the next server-initiated request
+    next_request_id: Arc<AtomicU64>,
+    /// server-initiated requests waiting on the client, keyed by their serialized json-rpc id
+    pending: Arc<Mutex<HashMap<String, ResponseSender>>>,
+}
+
+impl Session {
+    /// create a new session, returning the receiving end for messages sent to the client
+    pub fn new() -> (Self, UnboundedReceiver<Value>) {
+        let (outgoing, rx) = unbounded_channel();
+        let session = Session {
+            outgoing,
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            log_level: Arc::new(Mutex::new(DEFAULT_LOG_LEVEL)),
+            payout_totals: Arc::new(Mutex::new(PayoutTotals::default())),
+            client_capabilities: Arc::new(Mutex::new(ClientCapabilities::default())),
+            next_request_id: Arc::new(AtomicU64::new(0)),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+        };
+        (session, rx)
+    }
+
+    /// the same session, with messages sent through it going to `outgoing` instead, used to
+    /// stream what a single request sends on that request's own response
+    pub fn with_outgoing(&self, outgoing: UnboundedSender<Value>) -> Session {
+        Session {
+            outgoing,
+            ..self.clone()
+        }
+    }
+
+    /// queue a server-initiated message for the client, returns false if the transport has
+    /// gone away and there is nothing left to deliver to
+    pub fn send(&self, message: Value) -> bool {
+        self.outgoing.send(message).is_ok()
+    }
+
+    /// set the minimum level of log messages sent to the client
+    pub fn set_log_level(&self, level: LoggingLevel) {
+        *self.log_level.lock().unwrap() = level;
+    }
+
+    /// send a `notifications/message` to the client if it is at or above the selected level
+    pub fn log(&self, level: LoggingLevel, logger: &str, data: Value) {
+        if level < *self.log_level.lock().unwrap() {
+            return;
+        }
+        let params = LoggingMessageNotification {
+            level,
+            logger: logger.to_string(),
+            data,
+        };
+        notify(
+            self,
+            "notifications/message",
+            Some(serde_json::to_value(params).unwrap()),
+        );
+    }
+
+    /// remember the capabilities the client sent in `initialize`
+    pub fn set_client_capabilities(&self, capabilities: ClientCapabilities) {
+        *self.client_capabilities.lock().unwrap() = capabilities;
+    }
+
+    pub fn client_capabilities(&self) -> ClientCapabilities {
+        self.client_capabilities.lock().unwrap().clone()
+    }
+
+    /// send a request to the client and wait up to `timeout` for its result, or the error it
+    /// responded with
+    pub async fn request(
+        &self,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> Result<Value, RequestError> {
+        let id = json!(self.next_request_id.fetch_add(1, Ordering::Relaxed));
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id.to_string(), tx);
+        let sent = self.send(json!({
+            "jsonrpc": JSONRPC_VERSION,
+            "id": id,
+            "method": method,
+            "params": params,
+        }));
+        if !sent {
+            self.pending.lock().unwrap().remove(&id.to_string());
+            return Err(RequestError::Disconnected);
+        }
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(Ok(result))) => Ok(result),
+            Ok(Ok(Err(error))) => Err(RequestError::Response(error)),
+            Ok(Err(_)) => Err(RequestError::Disconnected),
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&id.to_string());
+                Err(RequestError::TimedOut)
+            }
+        }
+    }
+
+    /// route a response from the client to the request waiting on it
+    pub fn handle_response(&self, response: &Value) {
+        let Some(id) = response.get("id") else {
+            return;
+        };
+        let Some(tx) = self.pending.lock().unwrap().remove(&id.to_string()) else {
+            return;
+        };
+        let result = match response.get("error") {
+            Some(error) => Err(error.clone()),
+            None => Ok(response.get("result").cloned().unwrap_or_default()),
+        };
+        let _ = tx.send(result);
+    }
+
+    /// fail every request still waiting on the client, used once it has gone away
+    pub fn close_pending_requests(&self) {
+        self.pending.lock().unwrap().clear();
+    }
+
+    /// sats paid out to this client so far
+    pub fn payout_totals(&self) -> MutexGuard<'_, PayoutTotals> {
+        self.payout_totals.lock().unwrap()
+    }
+
+    /// track a request as soon as it is read, before it is running, so a cancellation that
+    /// arrives first still applies
+    pub fn start_request(&self, id: &Value) {
+        self.in_flight.lock().unwrap().insert(id.to_string(), None);
+    }
+
+    /// attach the task running a request so it can be aborted by a `notifications/cancelled`,
+    /// returns false and aborts it if the request was cancelled before it started running
+    pub fn register_request(&self, id: &Value, handle: AbortHandle) -> bool {
+        match self.in_flight.lock().unwrap().get_mut(&id.to_string()) {
+            Some(entry) => {
+                *entry = Some(handle);
+                true
+            }
+            None => {
+                handle.abort();
+                false
+            }
+        }
+    }
+
+    /// stop tracking a request once it has finished
+    pub fn complete_request(&self, id: &Value) {
+        self.in_flight.lock().unwrap().remove(&id.to_string());
+    }
+
+    pub fn has_in_flight_requests(&self) -> bool {
+        !self.in_flight.lock().unwrap().is_empty()
+    }
+
+    /// abort an in-flight request, returns false if it is unknown or already finished
+    pub fn cancel_request(&self, id: &Value) -> bool {
+        match self.in_flight.lock().unwrap().remove(&id.to_string()) {
+            Some(handle) => {
+                // not running yet, `register_request` aborts it once it is
+                if let Some(handle) = handle {
+                    handle.abort();
+                }
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Sends `notifications/progress` for the current request, if the client supplied a progress token
+#[derive(Clone, RpcResource)]
+pub struct ProgressReporter {
+    session: Session,
+    progress_token: Option<Value>,
+}
+
+impl ProgressReporter {
+    pub fn new(session: Session, progress_token: Option<Value>) -> Self {
+        ProgressReporter {
+            session,
+            progress_token,
+        }
+    }
+
+    /// report progress, `progress` must increase with every call for the same request
+    pub fn report(&self, progress: u64, total: Option<u64>, message: impl Into<String>) {
+        let Some(progress_token) = self.progress_token.clone() else {
+            return;
+        };
+        let params = Progress {
+            progress_token,
+            progress,
+            total,
+            message: Some(message.into()),
+        };
+        notify(
+            &self.session,
+            "notifications/progress",
+            Some(serde_json::to_value(params).unwrap()),
+        );
+    }
+}
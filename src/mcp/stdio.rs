@@ -0,0 +1,75 @@
+use crate::mcp::server::{accept_message, process_message};
+use crate::mcp::session::Session;
+use crate::mcp::transcript::Transcript;
+use rpc_router::Router;
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Stdout};
+use tokio::sync::oneshot;
+use tokio::task::JoinSet;
+
+/// serve the MCP router over stdin/stdout, one json-rpc message per line
+pub async fn serve(router: Router, transcript: Option<Transcript>) -> std::io::Result<()> {
+    let (session, mut outgoing) = Session::new();
+
+    // single writer so responses and notifications never interleave on stdout
+    let (shutdown_tx, mut shutdown_rx) = oneshot::channel::<()>();
+    let writer_transcript = transcript.clone();
+    let writer = tokio::spawn(async move {
+        let mut stdout = tokio::io::stdout();
+        loop {
+            tokio::select! {
+                biased;
+                Some(message) = outgoing.recv() => {
+                    write_message(&mut stdout, writer_transcript.as_ref(), &message).await?;
+                }
+                _ = &mut shutdown_rx => break,
+            }
+        }
+        // flush whatever was queued before shutdown, background tasks may still hold the session
+        while let Ok(message) = outgoing.try_recv() {
+            write_message(&mut stdout, writer_transcript.as_ref(), &message).await?;
+        }
+        Ok::<_, std::io::Error>(())
+    });
+
+    let mut requests = JoinSet::new();
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    while let Some(line) = lines.next_line().await? {
+        if let Some(transcript) = &transcript {
+            transcript.record_line(&line);
+        }
+        if let Ok(json_value) = serde_json::from_str::<Value>(&line) {
+            // dispatch each request on its own task, responses may complete out of order
+            accept_message(&session, &json_value);
+            let router = router.clone();
+            let session = session.clone();
+            requests.spawn(async move {
+                if let Some(response) = process_message(&router, &session, json_value).await {
+                    session.send(response);
+                }
+            });
+        }
+        while requests.try_join_next().is_some() {}
+    }
+
+    // stdin closed, nobody is left to answer our requests, so let in-flight
+    // requests finish writing before exiting
+    session.close_pending_requests();
+    while requests.join_next().await.is_some() {}
+    let _ = shutdown_tx.send(());
+    writer.await.unwrap()
+}
+
+async fn write_message(
+    stdout: &mut Stdout,
+    transcript: Option<&Transcript>,
+    message: &Value,
+) -> std::io::Result<()> {
+    if let Some(transcript) = transcript {
+        transcript.record(message);
+    }
+    let message_json = serde_json::to_string(message).unwrap();
+    stdout.write_all(message_json.as_bytes()).await?;
+    stdout.write_all(b"\n").await?;
+    stdout.flush().await
+}
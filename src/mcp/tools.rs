@@ -1,19 +1,61 @@
+use crate::mcp::address::{ValidatedAddress, validate_address};
+use crate::mcp::bip21::parse_bip21;
+use crate::mcp::channel::{
+    ChannelRequest, MAX_CHANNEL_CAPACITY, MIN_CHANNEL_CAPACITY, parse_channel_request,
+};
+use crate::mcp::destination::{DestinationType, classify};
+use crate::mcp::esplora::{EsploraClient, EsploraError, TxStatus};
+use crate::mcp::faucet::{
+    ChannelResponse, DeviceLoginResponse, FaucetClient, FaucetError, LightningResponse,
+    OnChainResponse,
+};
+use crate::mcp::idempotency::{self, Claim, KeyGuard};
+use crate::mcp::invoice::decode_invoice;
+use crate::mcp::ledger::{self, Payout, PayoutFilter, parse_date_bound};
+use crate::mcp::lnurl::{self, LnurlClient};
+use crate::mcp::offer::{DecodedOffer, decode_offer};
+use crate::mcp::policy::{PayoutKind, Policy, Reservation};
+use crate::mcp::session::{ProgressReporter, Session};
+use crate::mcp::transaction::decode_transaction;
 use crate::mcp::types::*;
 use crate::mcp::utilities;
+use bitcoin::Txid;
+use chrono::Utc;
 use maplit::hashmap;
-use reqwest::{Client, StatusCode};
-use rpc_router::{Handler, HandlerResult, IntoHandlerError, RouterBuilder, RpcParams};
+use rpc_router::{
+    Handler, HandlerError, HandlerResult, IntoHandlerError, RouterBuilder, RpcParams,
+};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
-use std::time::SystemTime;
+use serde_json::{Value, json};
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime};
+use url::Url;
+
+/// how long `pay_mutinynet_address` waits for confirmations unless told otherwise
+const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_secs(600);
+/// longest `wait_timeout_secs` accepted, anything longer is almost certainly a mistake
+const MAX_WAIT_TIMEOUT: Duration = Duration::from_secs(86_400);
+/// mutinynet has 30 second blocks
+const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// how many of an address's transactions `get_address_info` lists
+const RECENT_TRANSACTIONS: usize = 10;
 
 /// register all tools to the router
 pub fn register_tools(router_builder: RouterBuilder) -> RouterBuilder {
     router_builder
         .append_dyn("tools/list", tools_list.into_dyn())
         .append_dyn("login", login.into_dyn())
+        .append_dyn("fund", fund.into_dyn())
         .append_dyn("pay_mutinynet_invoice", pay_mutinynet_invoice.into_dyn())
+        .append_dyn("pay_mutinynet_offer", pay_mutinynet_offer.into_dyn())
         .append_dyn("pay_mutinynet_address", pay_mutinynet_address.into_dyn())
+        .append_dyn("open_channel", open_channel.into_dyn())
+        .append_dyn("list_payouts", list_payouts.into_dyn())
+        .append_dyn("get_transaction_status", get_transaction_status.into_dyn())
+        .append_dyn("get_address_info", get_address_info.into_dyn())
+        .append_dyn("get_chain_tip", get_chain_tip.into_dyn())
+        .append_dyn("get_fee_estimates", get_fee_estimates.into_dyn())
+        .append_dyn("broadcast_transaction", broadcast_transaction.into_dyn())
 }
 
 pub async fn tools_list(_request: Option<ListToolsRequest>) -> HandlerResult<ListToolsResult> {
@@ -29,268 +71,1544 @@ pub async fn tools_list(_request: Option<ListToolsRequest>) -> HandlerResult<Lis
             required: vec![],
         },
     };
+    let fund = Tool {
+        name: "fund".to_string(),
+        description: Some(
+            "Pays any mutinynet destination from the faucet: a bitcoin address, BIP21 URI, bolt11 invoice, bolt12 offer, lightning address or LNURL. The destination type is detected and paid with the matching tool, within the configured spend limits"
+                .to_string(),
+        ),
+        input_schema: ToolInputSchema {
+            type_name: "object".to_string(),
+            properties: hashmap! {
+                "destination".to_string() => ToolInputSchemaProperty {
+                    type_name: Some("string".to_owned()),
+                    description: Some("Where to send the sats".to_owned()),
+                    enum_values: None,
+                },
+                "amount".to_string() => ToolInputSchemaProperty {
+                    type_name: Some("number".to_owned()),
+                    description: Some("The amount in satoshis, needed for lightning addresses, LNURLs and offers without an amount. Addresses default to 5k sats and bolt11 invoices always pay their own amount".to_owned()),
+                    enum_values: None,
+                },
+                "dry_run".to_string() => dry_run_property(),
+                "idempotency_key".to_string() => idempotency_key_property(),
+            },
+            required: vec!["destination".to_string()],
+        },
+    };
     let pay_mutinynet_invoice = Tool {
         name: "pay_mutinynet_invoice".to_string(),
-        description: Some("Pays the given mutinynet invoice".to_string()),
+        description: Some(
+            "Pays the given mutinynet (signet, lntbs) bolt11 invoice, lightning address or LNURL. The invoice must have an amount, not be expired and be within the configured spend limits"
+                .to_string(),
+        ),
         input_schema: ToolInputSchema {
             type_name: "object".to_string(),
             properties: hashmap! {
                 "invoice".to_string() => ToolInputSchemaProperty {
                     type_name: Some("string".to_owned()),
-                    description: Some("Mutinynet invoice to pay".to_owned()),
+                    description: Some("Mutinynet invoice, lightning address (user@domain) or LNURL to pay".to_owned()),
                     enum_values: None,
-                }
+                },
+                "amount".to_string() => ToolInputSchemaProperty {
+                    type_name: Some("number".to_owned()),
+                    description: Some("The amount in satoshis to pay, required for lightning addresses and LNURLs and ignored for invoices".to_owned()),
+                    enum_values: None,
+                },
+                "dry_run".to_string() => dry_run_property(),
+                "idempotency_key".to_string() => idempotency_key_property(),
             },
             required: vec!["invoice".to_string()],
         },
     };
+    let pay_mutinynet_offer = Tool {
+        name: "pay_mutinynet_offer".to_string(),
+        description: Some(
+            "Pays the given mutinynet (signet) bolt12 offer. The offer must not be expired and the amount must be within the configured spend limits"
+                .to_string(),
+        ),
+        input_schema: ToolInputSchema {
+            type_name: "object".to_string(),
+            properties: hashmap! {
+                "offer".to_string() => ToolInputSchemaProperty {
+                    type_name: Some("string".to_owned()),
+                    description: Some("Mutinynet offer to pay, starting with lno".to_owned()),
+                    enum_values: None,
+                },
+                "amount".to_string() => ToolInputSchemaProperty {
+                    type_name: Some("number".to_owned()),
+                    description: Some("The amount in satoshis to pay, required if the offer has no amount and can't be below the offer's amount".to_owned()),
+                    enum_values: None,
+                },
+                "dry_run".to_string() => dry_run_property(),
+                "idempotency_key".to_string() => idempotency_key_property(),
+            },
+            required: vec!["offer".to_string()],
+        },
+    };
     let pay_mutinynet_address = Tool {
         name: "pay_mutinynet_address".to_string(),
-        description: Some("Pays the given mutinynet address".to_string()),
+        description: Some(
+            "Pays the given mutinynet (signet) address or BIP21 URI, the address must start with tb1, 2, m or n. The amount must be within the configured spend limits"
+                .to_string(),
+        ),
         input_schema: ToolInputSchema {
             type_name: "object".to_string(),
             properties: hashmap! {
                 "address".to_string() => ToolInputSchemaProperty {
                     type_name: Some("string".to_owned()),
-                    description: Some("Mutinynet address to pay".to_owned()),
+                    description: Some("Mutinynet address or BIP21 URI (bitcoin:tb1...?amount=0.0005) to pay".to_owned()),
                     enum_values: None,
                 },
                 "amount".to_string() => ToolInputSchemaProperty {
                     type_name: Some("number".to_owned()),
-                    description: Some("The amount in satoshis to pay the address, if none is given 5k sats will be used".to_owned()),
+                    description: Some("The amount in satoshis to pay the address, if none is given the BIP21 URI's amount or 5k sats will be used".to_owned()),
+                    enum_values: None,
+                },
+                "prefer_lightning".to_string() => ToolInputSchemaProperty {
+                    type_name: Some("boolean".to_owned()),
+                    description: Some("Pay the lightning invoice embedded in a BIP21 URI instead of the address, if it has one".to_owned()),
+                    enum_values: None,
+                },
+                "dry_run".to_string() => dry_run_property(),
+                "idempotency_key".to_string() => idempotency_key_property(),
+                "wait_confirmations".to_string() => ToolInputSchemaProperty {
+                    type_name: Some("integer".to_owned()),
+                    description: Some("Wait until the on-chain payout has this many confirmations before returning, reporting progress on the way, not allowed when prefer_lightning pays a lightning invoice".to_owned()),
+                    enum_values: None,
+                },
+                "wait_timeout_secs".to_string() => ToolInputSchemaProperty {
+                    type_name: Some("integer".to_owned()),
+                    description: Some(format!("How long to wait for confirmations, defaults to {} seconds, at most {}", DEFAULT_WAIT_TIMEOUT.as_secs(), MAX_WAIT_TIMEOUT.as_secs())),
+                    enum_values: None,
+                },
+            },
+            required: vec!["address".to_string()],
+        },
+    };
+    let open_channel = Tool {
+        name: "open_channel".to_string(),
+        description: Some(
+            "Opens a lightning channel from the mutinynet faucet's node to the given node"
+                .to_string(),
+        ),
+        input_schema: ToolInputSchema {
+            type_name: "object".to_string(),
+            properties: hashmap! {
+                "node".to_string() => ToolInputSchemaProperty {
+                    type_name: Some("string".to_owned()),
+                    description: Some("Node to open the channel to, as pubkey@host:port".to_owned()),
+                    enum_values: None,
+                },
+                "capacity".to_string() => ToolInputSchemaProperty {
+                    type_name: Some("number".to_owned()),
+                    description: Some(format!(
+                        "Size of the channel in satoshis, between {MIN_CHANNEL_CAPACITY} and {MAX_CHANNEL_CAPACITY}"
+                    )),
+                    enum_values: None,
+                },
+                "push_amount".to_string() => ToolInputSchemaProperty {
+                    type_name: Some("number".to_owned()),
+                    description: Some("Satoshis to give the node when the channel opens, defaults to 0".to_owned()),
+                    enum_values: None,
+                },
+                "dry_run".to_string() => dry_run_property(),
+                "idempotency_key".to_string() => idempotency_key_property(),
+            },
+            required: vec!["node".to_string(), "capacity".to_string()],
+        },
+    };
+    let list_payouts = Tool {
+        name: "list_payouts".to_string(),
+        description: Some(
+            "Lists payouts previously made by the faucet, oldest first, optionally filtered"
+                .to_string(),
+        ),
+        input_schema: ToolInputSchema {
+            type_name: "object".to_string(),
+            properties: hashmap! {
+                "kind".to_string() => ToolInputSchemaProperty {
+                    type_name: Some("string".to_owned()),
+                    description: Some(
+                        "Only return on-chain, lightning or channel payouts".to_owned(),
+                    ),
+                    enum_values: Some(vec![
+                        "onchain".to_owned(),
+                        "lightning".to_owned(),
+                        "channel".to_owned(),
+                    ]),
+                },
+                "destination".to_string() => ToolInputSchemaProperty {
+                    type_name: Some("string".to_owned()),
+                    description: Some("Only return payouts to this address or invoice".to_owned()),
+                    enum_values: None,
+                },
+                "since".to_string() => ToolInputSchemaProperty {
+                    type_name: Some("string".to_owned()),
+                    description: Some("Only return payouts made on or after this date (YYYY-MM-DD) or RFC 3339 timestamp".to_owned()),
+                    enum_values: None,
+                },
+                "until".to_string() => ToolInputSchemaProperty {
+                    type_name: Some("string".to_owned()),
+                    description: Some("Only return payouts made on or before this date (YYYY-MM-DD), or before this RFC 3339 timestamp".to_owned()),
+                    enum_values: None,
+                },
+            },
+            required: vec![],
+        },
+    };
+    let get_transaction_status = Tool {
+        name: "get_transaction_status".to_string(),
+        description: Some(
+            "Looks up a mutinynet transaction, returning its confirmations, block, fee and outputs"
+                .to_string(),
+        ),
+        input_schema: ToolInputSchema {
+            type_name: "object".to_string(),
+            properties: hashmap! {
+                "txid".to_string() => ToolInputSchemaProperty {
+                    type_name: Some("string".to_owned()),
+                    description: Some("Id of the transaction, e.g. the one returned by pay_mutinynet_address".to_owned()),
+                    enum_values: None,
+                },
+            },
+            required: vec!["txid".to_string()],
+        },
+    };
+    let get_address_info = Tool {
+        name: "get_address_info".to_string(),
+        description: Some(
+            "Looks up a mutinynet address, returning its confirmed and unconfirmed balance, unspent outputs and recent transactions"
+                .to_string(),
+        ),
+        input_schema: ToolInputSchema {
+            type_name: "object".to_string(),
+            properties: hashmap! {
+                "address".to_string() => ToolInputSchemaProperty {
+                    type_name: Some("string".to_owned()),
+                    description: Some("Mutinynet (signet) bitcoin address to look up".to_owned()),
                     enum_values: None,
                 },
             },
             required: vec!["address".to_string()],
         },
     };
+    let get_chain_tip = Tool {
+        name: "get_chain_tip".to_string(),
+        description: Some(
+            "Returns the height, hash and time of the latest mutinynet block and the size of the mempool"
+                .to_string(),
+        ),
+        input_schema: ToolInputSchema {
+            type_name: "object".to_string(),
+            properties: hashmap! {},
+            required: vec![],
+        },
+    };
+    let get_fee_estimates = Tool {
+        name: "get_fee_estimates".to_string(),
+        description: Some(
+            "Returns the fee rates in sat/vB needed to confirm a mutinynet transaction within a number of blocks"
+                .to_string(),
+        ),
+        input_schema: ToolInputSchema {
+            type_name: "object".to_string(),
+            properties: hashmap! {},
+            required: vec![],
+        },
+    };
+    let broadcast_transaction = Tool {
+        name: "broadcast_transaction".to_string(),
+        description: Some(
+            "Checks a signed mutinynet transaction and broadcasts it, returning its transaction id. Its inputs must exist on mutinynet and the fee must be sane. Output scripts don't say which network they are for, so outputs are shown as mutinynet addresses but their network isn't checked"
+                .to_string(),
+        ),
+        input_schema: ToolInputSchema {
+            type_name: "object".to_string(),
+            properties: hashmap! {
+                "hex".to_string() => ToolInputSchemaProperty {
+                    type_name: Some("string".to_owned()),
+                    description: Some("The signed transaction, hex encoded".to_owned()),
+                    enum_values: None,
+                },
+            },
+            required: vec!["hex".to_string()],
+        },
+    };
     let response = ListToolsResult {
-        tools: vec![login, pay_mutinynet_invoice, pay_mutinynet_address],
+        tools: vec![
+            login,
+            fund,
+            pay_mutinynet_invoice,
+            pay_mutinynet_offer,
+            pay_mutinynet_address,
+            open_channel,
+            list_payouts,
+            get_transaction_status,
+            get_address_info,
+            get_chain_tip,
+            get_fee_estimates,
+            broadcast_transaction,
+        ],
         next_cursor: None,
     };
     Ok(response)
 }
 
-const GH_CLIENT_ID: &str = "Ov23liIa6qfR9KtYHwUF";
-const GH_SCOPE: &str = "user:email";
-
 #[derive(Deserialize, Serialize, RpcParams)]
 pub struct LoginRequest {}
 
-#[derive(Deserialize)]
-struct DeviceLoginResponse {
-    device_code: String,
-    user_code: String,
-    verification_uri: String,
-    expires_in: u64,
-    interval: u64,
+pub async fn login(
+    faucet: FaucetClient,
+    session: Session,
+    progress: ProgressReporter,
+    _: LoginRequest,
+) -> HandlerResult<CallToolResult> {
+    let resp = faucet
+        .request_device_code()
+        .await
+        .map_err(|_| json!({"code": -32603, "message": "Internal error"}).into_handler_error())?;
+
+    let text = format!(
+        "Open this url: {} and enter the code: {} to login",
+        resp.verification_uri, resp.user_code
+    );
+    session.log(
+        LoggingLevel::Info,
+        "auth",
+        json!({ "message": "Started github device login", "verification_uri": resp.verification_uri }),
+    );
+
+    // the code always goes in the result, clients don't have to show progress messages, and the
+    // oauth login is watched in the background since the user can't enter it while we block
+    progress.report(0, None, text.clone());
+    tokio::spawn(wait_for_device_authorization(faucet, resp, session));
+    Ok(CallToolResult::text(text))
 }
 
-#[derive(Deserialize)]
-struct AccessTokenResponse {
-    access_token: String,
+/// poll github until the device code is authorized, then exchange it for a faucet token
+async fn wait_for_device_authorization(
+    faucet: FaucetClient,
+    resp: DeviceLoginResponse,
+    session: Session,
+) {
+    let start = SystemTime::now();
+    let deadline = resp.expires_in + 10;
+    while SystemTime::now().duration_since(start).unwrap().as_secs() < deadline {
+        if let Some(token) = faucet.poll_device_authorization(&resp.device_code).await {
+            utilities::write_bearer_token(token);
+            session.log(
+                LoggingLevel::Notice,
+                "auth",
+                json!({ "message": "Logged in to the faucet" }),
+            );
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(resp.interval)).await;
+    }
+    session.log(
+        LoggingLevel::Warning,
+        "auth",
+        json!({ "message": "Github device code expired before it was authorized" }),
+    );
 }
 
-#[derive(Deserialize)]
-struct DeviceReturn {
-    token: String,
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct PayInvoiceRequest {
+    invoice: String,
+    amount: Option<u64>,
+    dry_run: Option<bool>,
+    idempotency_key: Option<String>,
 }
 
-pub async fn login(_: LoginRequest) -> HandlerResult<CallToolResult> {
-    let client = Client::new();
-    let resp: DeviceLoginResponse = client
-        .post("https://github.com/login/device/code")
-        .json(&json!({
-            "client_id": GH_CLIENT_ID,
-            "scope": GH_SCOPE,
-        }))
-        .header("Content-Type", "application/json")
-        .header("Accept", "application/json")
-        .send()
-        .await
-        .map_err(|_| json!({"code": -32603, "message": "Internal error"}).into_handler_error())?
-        .json()
-        .await
-        .map_err(|_| json!({"code": -32603, "message": "Internal error"}).into_handler_error())?;
+pub async fn pay_mutinynet_invoice(
+    faucet: FaucetClient,
+    policy: Policy,
+    lnurl: LnurlClient,
+    session: Session,
+    progress: ProgressReporter,
+    req: PayInvoiceRequest,
+) -> HandlerResult<CallToolResult> {
+    // lightning addresses and LNURLs are only resolved to an invoice once the payout is allowed,
+    // until then they are known by their name and the amount asked for
+    let target = match lnurl::parse_target(&req.invoice).transpose() {
+        Ok(target) => target,
+        Err(text) => {
+            return Ok(CallToolResult::error(text));
+        }
+    };
+    let (destination, sats, decoded) = match &target {
+        Some(target) => {
+            let Some(amount) = req.amount.filter(|amount| *amount > 0) else {
+                let text = format!("An amount in sats is needed to pay {}", target.name);
+                return Ok(CallToolResult::error(text));
+            };
+            (target.name.clone(), amount, None)
+        }
+        None => match decode_invoice(&req.invoice) {
+            Ok(decoded) => (decoded.invoice.clone(), decoded.amount_sats, Some(decoded)),
+            Err(text) => {
+                return Ok(CallToolResult::error(text));
+            }
+        },
+    };
+    let key_guard = match idempotency::claim(
+        req.idempotency_key.as_deref(),
+        PayoutKind::Lightning,
+        &destination,
+        sats,
+    ) {
+        Ok(Claim::Paid(payout)) => {
+            let payment_hash = payout.payment_hash.as_deref().unwrap_or_default();
+            let summary = match &decoded {
+                Some(decoded) => decoded.summary(),
+                None => format!("Recipient: {destination}\nAmount: {sats} sats"),
+            };
+            let text = lightning_success_text(payment_hash, &summary);
+            return Ok(replayed_result(payout, text));
+        }
+        Ok(Claim::New(guard)) => guard,
+        Err(text) => {
+            return Ok(CallToolResult::error(text));
+        }
+    };
+    // given back when dropped, unless the payout is made
+    let reservation = match policy.reserve(&session, PayoutKind::Lightning, sats) {
+        Ok(reservation) => reservation,
+        Err(text) => {
+            return Ok(CallToolResult::error(text));
+        }
+    };
 
-    let start = SystemTime::now();
+    let (decoded, summary) = match (decoded, &target) {
+        (Some(decoded), _) => {
+            let summary = decoded.summary();
+            (decoded, summary)
+        }
+        (None, Some(target)) => match lnurl.fetch_invoice(target, sats).await {
+            Ok(decoded) => {
+                let summary = format!("Recipient: {destination}\n{}", decoded.summary());
+                (decoded, summary)
+            }
+            Err(text) => {
+                return Ok(CallToolResult::error(text));
+            }
+        },
+        (None, None) => unreachable!("invoices are decoded up front"),
+    };
 
-    let text = format!(
-        "Open this url: {} and enter the code: {} to login",
-        resp.verification_uri, resp.user_code
+    if policy.is_dry_run(req.dry_run) {
+        let (url, body) = faucet.lightning_request(&decoded.invoice);
+        return Ok(dry_run_result(&session, url, body, summary));
+    }
+
+    let confirmation = format!("Pay this mutinynet lightning invoice from the faucet?\n{summary}");
+    if let Err(text) = policy.confirm(&session, confirmation).await {
+        return Ok(CallToolResult::error(text));
+    }
+
+    let token = match utilities::get_bearer_token() {
+        Some(token) => token,
+        None => {
+            session.log(
+                LoggingLevel::Info,
+                "auth",
+                json!({ "message": "No faucet token found, logging in" }),
+            );
+            return login_required(faucet, session, progress).await;
+        }
+    };
+
+    progress.report(0, Some(2), "Requesting lightning payment from the faucet");
+    session.log(
+        LoggingLevel::Info,
+        "faucet",
+        json!({
+            "message": "Requesting lightning payment",
+            "invoice": decoded.invoice,
+            "sats": sats,
+        }),
+    );
+    let pay = {
+        let faucet = faucet.clone();
+        let invoice = decoded.invoice.clone();
+        async move { faucet.pay_lightning(&token, &invoice).await }
+    };
+    let idempotency_key = req.idempotency_key;
+    let record = move |res: &LightningResponse| Payout {
+        timestamp: Utc::now(),
+        kind: PayoutKind::Lightning,
+        destination,
+        sats,
+        txid: None,
+        payment_hash: Some(res.payment_hash.clone()),
+        idempotency_key,
+        push_amount: None,
+    };
+    let res = match make_payout(&session, pay, reservation, key_guard, record).await {
+        Ok(res) => res,
+        Err(e) => {
+            return match e {
+                FaucetError::Unauthorized => relogin(faucet, session, progress).await,
+                e => Err(faucet_error(e)),
+            };
+        }
+    };
+
+    progress.report(2, Some(2), "Payment complete");
+    session.log(
+        LoggingLevel::Info,
+        "faucet",
+        json!({ "message": "Lightning payment complete", "payment_hash": res.payment_hash }),
     );
+    let text = lightning_success_text(&res.payment_hash, &summary);
+    Ok(CallToolResult::text(text))
+}
 
-    // spawn thread to watch for oauth login
-    tokio::spawn(async move {
-        let json = json!({
-            "client_id": GH_CLIENT_ID,
-            "device_code": resp.device_code,
-            "grant_type": "urn:ietf:params:oauth:grant-type:device_code"
-        });
-        while SystemTime::now().duration_since(start).unwrap().as_secs() < resp.expires_in + 10 {
-            if let Ok(res) = client
-                .post("https://github.com/login/oauth/access_token")
-                .json(&json)
-                .header("Content-Type", "application/json")
-                .header("Accept", "application/json")
-                .send()
-                .await
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct PayOfferRequest {
+    offer: String,
+    amount: Option<u64>,
+    dry_run: Option<bool>,
+    idempotency_key: Option<String>,
+}
+
+pub async fn pay_mutinynet_offer(
+    faucet: FaucetClient,
+    policy: Policy,
+    session: Session,
+    progress: ProgressReporter,
+    req: PayOfferRequest,
+) -> HandlerResult<CallToolResult> {
+    let decoded = match decode_offer(&req.offer, req.amount) {
+        Ok(decoded) => decoded,
+        Err(text) => {
+            return Ok(CallToolResult::error(text));
+        }
+    };
+    let key_guard = match idempotency::claim(
+        req.idempotency_key.as_deref(),
+        PayoutKind::Lightning,
+        &decoded.offer,
+        decoded.amount_sats,
+    ) {
+        Ok(Claim::Paid(payout)) => {
+            let text =
+                offer_success_text(payout.payment_hash.as_deref().unwrap_or_default(), &decoded);
+            return Ok(replayed_result(payout, text));
+        }
+        Ok(Claim::New(guard)) => guard,
+        Err(text) => {
+            return Ok(CallToolResult::error(text));
+        }
+    };
+    // given back when dropped, unless the payout is made
+    let reservation = match policy.reserve(&session, PayoutKind::Lightning, decoded.amount_sats) {
+        Ok(reservation) => reservation,
+        Err(text) => {
+            return Ok(CallToolResult::error(text));
+        }
+    };
+
+    if policy.is_dry_run(req.dry_run) {
+        let (url, body) = faucet.bolt12_request(&decoded.offer, decoded.amount_sats);
+        return Ok(dry_run_result(&session, url, body, decoded.summary()));
+    }
+
+    let confirmation = format!(
+        "Pay this mutinynet bolt12 offer from the faucet?\n{}",
+        decoded.summary()
+    );
+    if let Err(text) = policy.confirm(&session, confirmation).await {
+        return Ok(CallToolResult::error(text));
+    }
+
+    let token = match utilities::get_bearer_token() {
+        Some(token) => token,
+        None => {
+            session.log(
+                LoggingLevel::Info,
+                "auth",
+                json!({ "message": "No faucet token found, logging in" }),
+            );
+            return login_required(faucet, session, progress).await;
+        }
+    };
+
+    progress.report(0, Some(2), "Requesting bolt12 payment from the faucet");
+    session.log(
+        LoggingLevel::Info,
+        "faucet",
+        json!({
+            "message": "Requesting bolt12 payment",
+            "offer": decoded.offer,
+            "sats": decoded.amount_sats,
+        }),
+    );
+    let pay = {
+        let faucet = faucet.clone();
+        let offer = decoded.offer.clone();
+        let sats = decoded.amount_sats;
+        async move { faucet.pay_bolt12(&token, &offer, sats).await }
+    };
+    let (offer, sats, idempotency_key) = (
+        decoded.offer.clone(),
+        decoded.amount_sats,
+        req.idempotency_key,
+    );
+    let record = move |res: &LightningResponse| Payout {
+        timestamp: Utc::now(),
+        kind: PayoutKind::Lightning,
+        destination: offer,
+        sats,
+        txid: None,
+        payment_hash: Some(res.payment_hash.clone()),
+        idempotency_key,
+        push_amount: None,
+    };
+    let res = match make_payout(&session, pay, reservation, key_guard, record).await {
+        Ok(res) => res,
+        Err(e) => {
+            return match e {
+                FaucetError::Unauthorized => relogin(faucet, session, progress).await,
+                e => Err(faucet_error(e)),
+            };
+        }
+    };
+
+    progress.report(2, Some(2), "Payment complete");
+    session.log(
+        LoggingLevel::Info,
+        "faucet",
+        json!({ "message": "Bolt12 payment complete", "payment_hash": res.payment_hash }),
+    );
+    let text = offer_success_text(&res.payment_hash, &decoded);
+    Ok(CallToolResult::text(text))
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct PayAddressRequest {
+    address: String,
+    amount: Option<u64>,
+    prefer_lightning: Option<bool>,
+    dry_run: Option<bool>,
+    idempotency_key: Option<String>,
+    wait_confirmations: Option<u32>,
+    wait_timeout_secs: Option<u64>,
+}
+
+pub async fn pay_mutinynet_address(
+    faucet: FaucetClient,
+    policy: Policy,
+    esplora: EsploraClient,
+    lnurl: LnurlClient,
+    session: Session,
+    progress: ProgressReporter,
+    mut req: PayAddressRequest,
+) -> HandlerResult<CallToolResult> {
+    if classify(&req.address) == Ok(DestinationType::Bip21) {
+        let uri = match parse_bip21(&req.address) {
+            Ok(uri) => uri,
+            Err(text) => {
+                return Ok(CallToolResult::error(text));
+            }
+        };
+        let amount = match uri.amount(req.amount) {
+            Ok(amount) => amount,
+            Err(text) => {
+                return Ok(CallToolResult::error(text));
+            }
+        };
+        if req.prefer_lightning.unwrap_or(false)
+            && let Some(invoice) = uri.lightning
+        {
+            // lightning payments don't confirm, refuse rather than return without the wait
+            if req
+                .wait_confirmations
+                .is_some_and(|confirmations| confirmations > 0)
             {
-                if let Ok(res) = res.json::<AccessTokenResponse>().await {
-                    if let Ok(res) = client
-                        .post("https://faucet.mutinynet.com/auth/github/device")
-                        .json(&json!({
-                            "code": res.access_token,
-                        }))
-                        .header("Content-Type", "application/json")
-                        .send()
-                        .await
-                    {
-                        if let Ok(device) = res.json::<DeviceReturn>().await {
-                            utilities::write_bearer_token(device.token);
-                            return;
+                let text = "wait_confirmations only applies to on-chain payouts, it can't be used when prefer_lightning pays the URI's lightning invoice".to_string();
+                return Ok(CallToolResult::error(text));
+            }
+            // the invoice is decoded again by the invoice tool, this only catches a uri
+            // whose two halves disagree on the amount
+            if let (Some(amount), Ok(decoded)) = (amount, decode_invoice(&invoice))
+                && decoded.amount_sats != amount
+            {
+                let text = format!(
+                    "Lightning invoice in the BIP21 URI is for {} sats, not the {amount} sats asked for",
+                    decoded.amount_sats
+                );
+                return Ok(CallToolResult::error(text));
+            }
+            // a lightning address or LNURL needs the amount, an invoice already has its own
+            let req = PayInvoiceRequest {
+                invoice,
+                amount,
+                dry_run: req.dry_run,
+                idempotency_key: req.idempotency_key,
+            };
+            return pay_mutinynet_invoice(faucet, policy, lnurl, session, progress, req).await;
+        }
+        req.address = uri.address;
+        req.amount = amount;
+    }
+
+    let address = match validate_address(&req.address) {
+        Ok(address) => address,
+        Err(text) => {
+            return Ok(CallToolResult::error(text));
+        }
+    };
+
+    // checked before anything is paid, the wait only starts once the payout has been made
+    if let Some(timeout) = req.wait_timeout_secs
+        && timeout > MAX_WAIT_TIMEOUT.as_secs()
+    {
+        let text = format!(
+            "wait_timeout_secs of {timeout} is above the maximum of {} seconds",
+            MAX_WAIT_TIMEOUT.as_secs()
+        );
+        return Ok(CallToolResult::error(text));
+    }
+
+    let amount = req.amount.unwrap_or(5_000);
+    let wait = req
+        .wait_confirmations
+        .filter(|confirmations| *confirmations > 0)
+        .map(|confirmations| ConfirmationWait {
+            confirmations,
+            timeout: req
+                .wait_timeout_secs
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_WAIT_TIMEOUT),
+        });
+    let key_guard = match idempotency::claim(
+        req.idempotency_key.as_deref(),
+        PayoutKind::OnChain,
+        &address.address,
+        amount,
+    ) {
+        Ok(Claim::Paid(payout)) => {
+            let txid = payout.txid.clone().unwrap_or_default();
+            let text = onchain_success_text(&txid, &address);
+            let result = replayed_result(payout, text);
+            return Ok(
+                with_confirmations(&esplora, &session, &progress, &txid, wait, result).await,
+            );
+        }
+        Ok(Claim::New(guard)) => guard,
+        Err(text) => {
+            return Ok(CallToolResult::error(text));
+        }
+    };
+    // given back when dropped, unless the payout is made
+    let reservation = match policy.reserve(&session, PayoutKind::OnChain, amount) {
+        Ok(reservation) => reservation,
+        Err(text) => {
+            return Ok(CallToolResult::error(text));
+        }
+    };
+
+    if policy.is_dry_run(req.dry_run) {
+        let (url, body) = faucet.onchain_request(amount, &address.address);
+        let details = format!(
+            "Amount: {amount} sats\nAddress: {} ({})",
+            address.address, address.address_type
+        );
+        return Ok(dry_run_result(&session, url, body, details));
+    }
+
+    let confirmation = format!(
+        "Send {amount} sats on-chain from the mutinynet faucet?\nAddress: {} ({})",
+        address.address, address.address_type
+    );
+    if let Err(text) = policy.confirm(&session, confirmation).await {
+        return Ok(CallToolResult::error(text));
+    }
+
+    let token = match utilities::get_bearer_token() {
+        Some(token) => token,
+        None => {
+            session.log(
+                LoggingLevel::Info,
+                "auth",
+                json!({ "message": "No faucet token found, logging in" }),
+            );
+            return login_required(faucet, session, progress).await;
+        }
+    };
+
+    let total = 2 + wait.map_or(0, |wait| u64::from(wait.confirmations));
+    progress.report(0, Some(total), "Requesting on-chain payout from the faucet");
+    session.log(
+        LoggingLevel::Info,
+        "faucet",
+        json!({
+            "message": "Requesting on-chain payout",
+            "address": address.address,
+            "address_type": address.address_type,
+            "sats": amount,
+        }),
+    );
+    let pay = {
+        let faucet = faucet.clone();
+        let address = address.address.clone();
+        async move { faucet.pay_onchain(&token, amount, &address).await }
+    };
+    let (destination, idempotency_key) = (address.address.clone(), req.idempotency_key);
+    let record = move |res: &OnChainResponse| Payout {
+        timestamp: Utc::now(),
+        kind: PayoutKind::OnChain,
+        destination,
+        sats: amount,
+        txid: Some(res.txid.clone()),
+        payment_hash: None,
+        idempotency_key,
+        push_amount: None,
+    };
+    let res = match make_payout(&session, pay, reservation, key_guard, record).await {
+        Ok(res) => res,
+        Err(e) => {
+            return match e {
+                FaucetError::Unauthorized => relogin(faucet, session, progress).await,
+                e => Err(faucet_error(e)),
+            };
+        }
+    };
+
+    progress.report(2, Some(total), "Transaction broadcast");
+    session.log(
+        LoggingLevel::Info,
+        "faucet",
+        json!({ "message": "On-chain payout broadcast", "txid": res.txid }),
+    );
+    let text = onchain_success_text(&res.txid, &address);
+    let result = CallToolResult::text(text);
+    Ok(with_confirmations(&esplora, &session, &progress, &res.txid, wait, result).await)
+}
+
+/// How deep a payout has to be buried before `pay_mutinynet_address` returns
+#[derive(Clone, Copy)]
+struct ConfirmationWait {
+    confirmations: u32,
+    timeout: Duration,
+}
+
+/// wait for the payout to confirm if the client asked for it, adding where it confirmed to
+/// the result, the payout was made either way so running out of time isn't an error, which
+/// would have agents retry and pay twice
+async fn with_confirmations(
+    esplora: &EsploraClient,
+    session: &Session,
+    progress: &ProgressReporter,
+    txid: &str,
+    wait: Option<ConfirmationWait>,
+    mut result: CallToolResult,
+) -> CallToolResult {
+    let Some(wait) = wait else {
+        return result;
+    };
+    let line = match wait_for_confirmations(esplora, session, progress, txid, wait).await {
+        Ok(line) | Err(line) => line,
+    };
+    if let Some(CallToolResultContent::Text { text }) = result.content.first_mut() {
+        text.push('\n');
+        text.push_str(&line);
+    }
+    result
+}
+
+/// poll esplora until the transaction is buried deep enough, progress continues from the
+/// 2 steps of the payout itself
+async fn wait_for_confirmations(
+    esplora: &EsploraClient,
+    session: &Session,
+    progress: &ProgressReporter,
+    txid: &str,
+    wait: ConfirmationWait,
+) -> Result<String, String> {
+    let target = u64::from(wait.confirmations);
+    let deadline = Instant::now() + wait.timeout;
+    let mut reported = 0;
+    loop {
+        match esplora.get_transaction(txid).await {
+            Ok(tx) => {
+                if let (Some(height), Some(hash)) = (tx.status.block_height, tx.status.block_hash)
+                    && tx.status.confirmed
+                {
+                    match esplora.get_tip_height().await {
+                        Ok(tip) => {
+                            let confirmations = (tip + 1).saturating_sub(height);
+                            if confirmations >= target {
+                                progress.report(2 + target, Some(2 + target), "Confirmed");
+                                return Ok(format!(
+                                    "Confirmed in block {height} ({hash}), {confirmations} confirmations"
+                                ));
+                            }
+                            if confirmations > reported {
+                                reported = confirmations;
+                                progress.report(
+                                    2 + confirmations,
+                                    Some(2 + target),
+                                    format!("{confirmations} of {target} confirmations"),
+                                );
+                            }
                         }
+                        Err(e) => log_esplora_error(session, txid, e),
                     }
                 }
             }
+            // esplora hasn't seen the broadcast yet
+            Err(EsploraError::NotFound) => {}
+            Err(e) => log_esplora_error(session, txid, e),
+        }
 
-            tokio::time::sleep(std::time::Duration::from_secs(resp.interval)).await;
+        if Instant::now() + CONFIRMATION_POLL_INTERVAL > deadline {
+            return Err(format!(
+                "The payment was made, only the wait timed out after {} seconds for {target} confirmations, the transaction has {reported} so far",
+                wait.timeout.as_secs()
+            ));
         }
+        tokio::time::sleep(CONFIRMATION_POLL_INTERVAL).await;
+    }
+}
+
+fn log_esplora_error(session: &Session, txid: &str, e: EsploraError) {
+    session.log(
+        LoggingLevel::Warning,
+        "esplora",
+        json!({ "message": "Failed to check confirmations", "txid": txid, "error": e.to_string() }),
+    );
+}
+
+/// ask the faucet to pay on a task of its own that also records the payout, so cancelling the
+/// tool call can't land between the faucet paying and the ledger entry being written, after
+/// which a retry with the same idempotency key would pay a second time
+async fn make_payout<T, F>(
+    session: &Session,
+    pay: F,
+    reservation: Reservation,
+    key_guard: Option<KeyGuard>,
+    record: impl FnOnce(&T) -> Payout + Send + 'static,
+) -> Result<T, FaucetError>
+where
+    T: Send + 'static,
+    F: Future<Output = Result<T, FaucetError>> + Send + 'static,
+{
+    let session = session.clone();
+    let payout = tokio::spawn(async move {
+        let res = pay.await?;
+        reservation.commit();
+        record_payout(&session, record(&res));
+        // the key is only free for a retry once the payout is in the ledger
+        drop(key_guard);
+        Ok(res)
     });
+    match payout.await {
+        Ok(res) => res,
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
 
-    Ok(CallToolResult {
-        is_error: false,
-        content: vec![CallToolResultContent::Text { text }],
-    })
+/// add a payout to the ledger, the payment has already been made so a failure is only logged
+fn record_payout(session: &Session, payout: Payout) {
+    if let Err(e) = ledger::record_payout(&payout) {
+        session.log(
+            LoggingLevel::Warning,
+            "ledger",
+            json!({ "message": "Failed to record payout", "error": e.to_string(), "payout": payout }),
+        );
+    }
 }
 
 #[derive(Deserialize, Serialize, RpcParams)]
-pub struct PayInvoiceRequest {
-    invoice: String,
+pub struct OpenChannelRequest {
+    node: String,
+    capacity: u64,
+    push_amount: Option<u64>,
+    dry_run: Option<bool>,
+    idempotency_key: Option<String>,
 }
 
-#[derive(Deserialize)]
-pub struct LightningResponse {
-    pub payment_hash: String,
-}
+pub async fn open_channel(
+    faucet: FaucetClient,
+    policy: Policy,
+    session: Session,
+    progress: ProgressReporter,
+    req: OpenChannelRequest,
+) -> HandlerResult<CallToolResult> {
+    let channel = match parse_channel_request(&req.node, req.capacity, req.push_amount.unwrap_or(0))
+    {
+        Ok(channel) => channel,
+        Err(text) => {
+            return Ok(CallToolResult::error(text));
+        }
+    };
+
+    // the node uri is what the faucet opens the channel to, the whole capacity comes out of
+    // the faucet's funds so that is what the limits and the ledger count
+    let node = format!("{}@{}", channel.pubkey, channel.host);
+    let key_guard = match idempotency::claim(
+        req.idempotency_key.as_deref(),
+        PayoutKind::Channel,
+        &node,
+        channel.capacity,
+    ) {
+        Ok(Claim::Paid(payout)) if payout.push_amount != Some(channel.push_amount) => {
+            let text = format!(
+                "Idempotency key {} was already used for a channel pushing {} sats to {}",
+                payout.idempotency_key.unwrap_or_default(),
+                payout.push_amount.unwrap_or_default(),
+                payout.destination
+            );
+            return Ok(CallToolResult::error(text));
+        }
+        Ok(Claim::Paid(payout)) => {
+            let txid = payout.txid.clone().unwrap_or_default();
+            let text = channel_success_text(&txid, &channel);
+            return Ok(replayed_result(payout, text));
+        }
+        Ok(Claim::New(guard)) => guard,
+        Err(text) => {
+            return Ok(CallToolResult::error(text));
+        }
+    };
+    // given back when dropped, unless the channel is opened
+    let reservation = match policy.reserve(&session, PayoutKind::Channel, channel.capacity) {
+        Ok(reservation) => reservation,
+        Err(text) => {
+            return Ok(CallToolResult::error(text));
+        }
+    };
+
+    if policy.is_dry_run(req.dry_run) {
+        let (url, body) = faucet.channel_request(&channel);
+        return Ok(dry_run_result(&session, url, body, channel.summary()));
+    }
+
+    let confirmation = format!(
+        "Open this lightning channel from the faucet?\n{}",
+        channel.summary()
+    );
+    if let Err(text) = policy.confirm(&session, confirmation).await {
+        return Ok(CallToolResult::error(text));
+    }
 
-pub async fn pay_mutinynet_invoice(req: PayInvoiceRequest) -> HandlerResult<CallToolResult> {
     let token = match utilities::get_bearer_token() {
         Some(token) => token,
         None => {
-            return login(LoginRequest {}).await;
+            session.log(
+                LoggingLevel::Info,
+                "auth",
+                json!({ "message": "No faucet token found, logging in" }),
+            );
+            return login_required(faucet, session, progress).await;
+        }
+    };
+
+    progress.report(0, Some(2), "Requesting a channel from the faucet");
+    session.log(
+        LoggingLevel::Info,
+        "faucet",
+        json!({
+            "message": "Requesting channel open",
+            "pubkey": channel.pubkey,
+            "host": channel.host,
+            "capacity": channel.capacity,
+            "push_amount": channel.push_amount,
+        }),
+    );
+    let pay = {
+        let faucet = faucet.clone();
+        let channel = channel.clone();
+        async move { faucet.open_channel(&token, &channel).await }
+    };
+    let (capacity, push_amount) = (channel.capacity, channel.push_amount);
+    let idempotency_key = req.idempotency_key;
+    let record = move |res: &ChannelResponse| Payout {
+        timestamp: Utc::now(),
+        kind: PayoutKind::Channel,
+        destination: node,
+        sats: capacity,
+        txid: Some(res.txid.clone()),
+        payment_hash: None,
+        idempotency_key,
+        push_amount: Some(push_amount),
+    };
+    let res = match make_payout(&session, pay, reservation, key_guard, record).await {
+        Ok(res) => res,
+        Err(e) => {
+            return match e {
+                FaucetError::Unauthorized => relogin(faucet, session, progress).await,
+                e => Err(faucet_error(e)),
+            };
         }
     };
 
-    let client = Client::new();
+    progress.report(2, Some(2), "Channel opening");
+    session.log(
+        LoggingLevel::Info,
+        "faucet",
+        json!({ "message": "Channel funding transaction broadcast", "txid": res.txid }),
+    );
+    let text = channel_success_text(&res.txid, &channel);
+    Ok(CallToolResult::text(text))
+}
 
-    let resp = client
-        .post("https://faucet.mutinynet.com/api/lightning")
-        .json(&json!({
-            "bolt11": req.invoice,
-        }))
-        .header("Content-Type", "application/json")
-        .header("Authorization", format!("Bearer {token}"))
-        .send()
-        .await
-        .map_err(|_| {
-            json!({"code": -32603, "message": "Error making request"}).into_handler_error()
-        })?;
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct FundRequest {
+    destination: String,
+    amount: Option<u64>,
+    dry_run: Option<bool>,
+    idempotency_key: Option<String>,
+}
 
-    let status = resp.status();
-    if !status.is_success() {
-        if status == StatusCode::from_u16(401).unwrap() {
-            return login(LoginRequest {}).await;
+pub async fn fund(
+    faucet: FaucetClient,
+    policy: Policy,
+    esplora: EsploraClient,
+    lnurl: LnurlClient,
+    session: Session,
+    progress: ProgressReporter,
+    req: FundRequest,
+) -> HandlerResult<CallToolResult> {
+    let destination_type = match classify(&req.destination) {
+        Ok(destination_type) => destination_type,
+        Err(text) => {
+            return Ok(CallToolResult::error(text));
         }
+    };
+    let (tool, result) = match destination_type {
+        DestinationType::Address | DestinationType::Bip21 => {
+            let req = PayAddressRequest {
+                address: req.destination,
+                amount: req.amount,
+                prefer_lightning: None,
+                dry_run: req.dry_run,
+                idempotency_key: req.idempotency_key,
+                wait_confirmations: None,
+                wait_timeout_secs: None,
+            };
+            let result =
+                pay_mutinynet_address(faucet, policy, esplora, lnurl, session, progress, req).await;
+            ("pay_mutinynet_address", result)
+        }
+        DestinationType::Bolt11 | DestinationType::LightningAddress | DestinationType::Lnurl => {
+            let req = PayInvoiceRequest {
+                invoice: req.destination,
+                amount: req.amount,
+                dry_run: req.dry_run,
+                idempotency_key: req.idempotency_key,
+            };
+            let result = pay_mutinynet_invoice(faucet, policy, lnurl, session, progress, req).await;
+            ("pay_mutinynet_invoice", result)
+        }
+        DestinationType::Bolt12 => {
+            let req = PayOfferRequest {
+                offer: req.destination,
+                amount: req.amount,
+                dry_run: req.dry_run,
+                idempotency_key: req.idempotency_key,
+            };
+            let result = pay_mutinynet_offer(faucet, policy, session, progress, req).await;
+            ("pay_mutinynet_offer", result)
+        }
+    };
 
-        let text = resp.text().await.map_err(|_| {
-            json!({"code": -32603, "message": "Error decoding text"}).into_handler_error()
-        })?;
-
-        return Err(
-            json!({"code": -32603, "message": format!("Error ({status}): {text}")})
-                .into_handler_error(),
+    let mut result = result?;
+    let rail = match destination_type {
+        DestinationType::Address | DestinationType::Bip21 => "onchain",
+        _ => "lightning",
+    };
+    if let Some(CallToolResultContent::Text { text }) = result.content.first_mut() {
+        *text = format!(
+            "Detected a {}, using {tool}\n{text}",
+            destination_type.name()
         );
     }
+    // the payment tools mark the results where nothing was paid without it being an error
+    let outcome = if result.is_error {
+        "failed"
+    } else {
+        result
+            .structured_content
+            .as_ref()
+            .and_then(|structured| structured["outcome"].as_str())
+            .unwrap_or("paid")
+    };
+    let structured = json!({
+        "destination_type": destination_type,
+        "rail": rail,
+        "tool": tool,
+        "outcome": outcome,
+    });
+    Ok(result.with_structured_content(structured))
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct ListPayoutsRequest {
+    kind: Option<PayoutKind>,
+    destination: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+}
 
-    let res: LightningResponse = resp.json().await.map_err(|_| {
-        json!({"code": -32603, "message": "Error decoding response"}).into_handler_error()
+pub async fn list_payouts(req: ListPayoutsRequest) -> HandlerResult<CallToolResult> {
+    let parse = |value: Option<String>, end| value.map(|v| parse_date_bound(&v, end)).transpose();
+    let filter = match (parse(req.since, false), parse(req.until, true)) {
+        (Ok(since), Ok(until)) => PayoutFilter {
+            kind: req.kind,
+            destination: req.destination,
+            since,
+            until,
+        },
+        (Err(text), _) | (_, Err(text)) => {
+            return Ok(CallToolResult::error(text));
+        }
+    };
+
+    let payouts = ledger::read_payouts().map_err(|e| {
+        json!({"code": -32603, "message": format!("Failed to read payout history: {e}")})
+            .into_handler_error()
     })?;
+    let payouts: Vec<Payout> = payouts.into_iter().filter(|p| filter.matches(p)).collect();
+    let text = if payouts.is_empty() {
+        "No payouts found".to_string()
+    } else {
+        serde_json::to_string_pretty(&payouts).unwrap()
+    };
+    Ok(CallToolResult::text(text))
+}
 
-    let text = format!("Payment success! Preimage: {}", res.payment_hash);
-    Ok(CallToolResult {
-        is_error: false,
-        content: vec![CallToolResultContent::Text { text }],
-    })
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct GetTransactionStatusRequest {
+    txid: String,
+}
+
+pub async fn get_transaction_status(
+    esplora: EsploraClient,
+    req: GetTransactionStatusRequest,
+) -> HandlerResult<CallToolResult> {
+    let txid = match Txid::from_str(req.txid.trim()) {
+        Ok(txid) => txid.to_string(),
+        Err(_) => {
+            let text = format!("Invalid transaction id {}", req.txid);
+            return Ok(CallToolResult::error(text));
+        }
+    };
+    let tx = match esplora.get_transaction(&txid).await {
+        Ok(tx) => tx,
+        Err(EsploraError::NotFound) => {
+            let text = format!("Transaction {txid} not found");
+            return Ok(CallToolResult::error(text));
+        }
+        Err(e) => return Err(esplora_error(e)),
+    };
+
+    let status = match (tx.status.block_height, &tx.status.block_hash) {
+        (Some(height), Some(hash)) if tx.status.confirmed => {
+            let tip = esplora.get_tip_height().await.map_err(esplora_error)?;
+            let confirmations = (tip + 1).saturating_sub(height);
+            format!("confirmed ({confirmations} confirmations) in block {height} ({hash})")
+        }
+        _ => "unconfirmed, waiting in the mempool".to_string(),
+    };
+    let outputs: Vec<String> = tx
+        .vout
+        .iter()
+        .map(|out| match &out.scriptpubkey_address {
+            Some(address) => format!(
+                "- {} sats to {address} ({})",
+                out.value, out.scriptpubkey_type
+            ),
+            None => format!("- {} sats ({})", out.value, out.scriptpubkey_type),
+        })
+        .collect();
+    let text = format!(
+        "Transaction {}\nStatus: {status}\nFee: {} sats ({:.2} sat/vB)\nOutputs:\n{}",
+        tx.txid,
+        tx.fee,
+        tx.fee_rate(),
+        outputs.join("\n")
+    );
+    Ok(CallToolResult::text(text))
 }
 
 #[derive(Deserialize, Serialize, RpcParams)]
-pub struct PayAddressRequest {
+pub struct GetAddressInfoRequest {
     address: String,
-    amount: Option<u64>,
 }
 
-#[derive(Deserialize)]
-pub struct OnChainResponse {
-    pub txid: String,
+pub async fn get_address_info(
+    esplora: EsploraClient,
+    req: GetAddressInfoRequest,
+) -> HandlerResult<CallToolResult> {
+    let address = match validate_address(&req.address) {
+        Ok(address) => address,
+        Err(text) => {
+            return Ok(CallToolResult::error(text));
+        }
+    };
+    let (info, utxos, transactions) = tokio::try_join!(
+        esplora.get_address_info(&address.address),
+        esplora.get_address_utxos(&address.address),
+        esplora.get_address_transactions(&address.address),
+    )
+    .map_err(esplora_error)?;
+
+    let mut lines = vec![
+        format!("Address {} ({})", address.address, address.address_type),
+        format!("Confirmed balance: {} sats", info.chain_stats.balance()),
+        format!(
+            "Unconfirmed balance: {:+} sats",
+            info.mempool_stats.balance()
+        ),
+    ];
+    if utxos.is_empty() {
+        lines.push("UTXOs: none".to_string());
+    } else {
+        lines.push("UTXOs:".to_string());
+        lines.extend(utxos.iter().map(|utxo| {
+            format!(
+                "- {}:{} {} sats, {}",
+                utxo.txid,
+                utxo.vout,
+                utxo.value,
+                block_text(&utxo.status)
+            )
+        }));
+    }
+    let tx_count = info.chain_stats.tx_count + info.mempool_stats.tx_count;
+    if transactions.is_empty() {
+        lines.push("Recent transactions: none".to_string());
+    } else {
+        lines.push(format!(
+            "Recent transactions ({} of {tx_count}):",
+            transactions.len().min(RECENT_TRANSACTIONS)
+        ));
+        lines.extend(transactions.iter().take(RECENT_TRANSACTIONS).map(|tx| {
+            format!(
+                "- {} {:+} sats, {}",
+                tx.txid,
+                tx.net_value(&address.address),
+                block_text(&tx.status)
+            )
+        }));
+    }
+    Ok(CallToolResult::text(lines.join("\n")))
 }
 
-pub async fn pay_mutinynet_address(req: PayAddressRequest) -> HandlerResult<CallToolResult> {
-    let token = match utilities::get_bearer_token() {
-        Some(token) => token,
-        None => {
-            return login(LoginRequest {}).await;
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct GetChainTipRequest {}
+
+pub async fn get_chain_tip(
+    esplora: EsploraClient,
+    _req: GetChainTipRequest,
+) -> HandlerResult<CallToolResult> {
+    let tip = esplora.get_chain_tip().await.map_err(esplora_error)?;
+    let text = format!(
+        "Chain tip: block {} ({})\nMined at: {}\nMempool: {} transactions, {} vB, {} sats in fees",
+        tip.height,
+        tip.hash,
+        tip.timestamp.to_rfc3339(),
+        tip.mempool.count,
+        tip.mempool.vsize,
+        tip.mempool.total_fee
+    );
+    Ok(CallToolResult::text(text).with_structured_content(serde_json::to_value(&tip).unwrap()))
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct GetFeeEstimatesRequest {}
+
+pub async fn get_fee_estimates(
+    esplora: EsploraClient,
+    _req: GetFeeEstimatesRequest,
+) -> HandlerResult<CallToolResult> {
+    let estimates = esplora.get_fee_estimates().await.map_err(esplora_error)?;
+    let mut lines = vec!["Fee estimates (sat/vB):".to_string()];
+    lines.extend(estimates.iter().map(|estimate| {
+        let blocks = if estimate.target_blocks == 1 {
+            "block"
+        } else {
+            "blocks"
+        };
+        format!(
+            "- {} {blocks}: {:.2}",
+            estimate.target_blocks, estimate.sat_per_vbyte
+        )
+    }));
+    Ok(CallToolResult::text(lines.join("\n"))
+        .with_structured_content(json!({ "estimates": estimates })))
+}
+
+#[derive(Deserialize, Serialize, RpcParams)]
+pub struct BroadcastTransactionRequest {
+    hex: String,
+}
+
+pub async fn broadcast_transaction(
+    esplora: EsploraClient,
+    req: BroadcastTransactionRequest,
+) -> HandlerResult<CallToolResult> {
+    let tx = match decode_transaction(&req.hex) {
+        Ok(tx) => tx,
+        Err(text) => {
+            return Ok(CallToolResult::error(text));
         }
     };
-    let amount = req.amount.unwrap_or(5_000);
 
-    if amount > 1_000_00 {
-        let text = "Amount is too high, max send amount is 1,000,000 sats".to_string();
-        return Ok(CallToolResult {
-            is_error: true,
-            content: vec![CallToolResultContent::Text { text }],
-        });
+    // the fee can only be checked once we know what the inputs are worth
+    let mut input_value: u64 = 0;
+    for input in &tx.inputs {
+        let spent = match esplora.get_transaction(&input.txid.to_string()).await {
+            Ok(spent) => spent.vout.get(input.vout as usize).map(|out| out.value),
+            Err(EsploraError::NotFound) => None,
+            Err(e) => return Err(esplora_error(e)),
+        };
+        let Some(value) = spent else {
+            let text = format!("Input {input} spends an output that doesn't exist on mutinynet");
+            return Ok(CallToolResult::error(text));
+        };
+        let Some(total) = input_value.checked_add(value) else {
+            let text = "Transaction inputs add up to more than 21 million bitcoin".to_string();
+            return Ok(CallToolResult::error(text));
+        };
+        input_value = total;
     }
+    let fee = match tx.fee(input_value) {
+        Ok(fee) => fee,
+        Err(text) => {
+            return Ok(CallToolResult::error(text));
+        }
+    };
 
-    let client = Client::new();
+    let txid = match esplora.broadcast(&tx.hex).await {
+        Ok(txid) => txid,
+        Err(EsploraError::Status(_, text)) => {
+            let text = format!("Transaction was rejected: {text}");
+            return Ok(CallToolResult::error(text));
+        }
+        Err(e) => return Err(esplora_error(e)),
+    };
+    let text = format!(
+        "Broadcast transaction {txid}\nFee: {fee} sats ({:.2} sat/vB)\n{}",
+        tx.fee_rate(fee),
+        tx.summary()
+    );
+    Ok(CallToolResult::text(text))
+}
 
-    let resp = client
-        .post("https://faucet.mutinynet.com/api/onchain")
-        .json(&json!({
-            "sats": amount,
-            "address": req.address,
-        }))
-        .header("Content-Type", "application/json")
-        .header("Authorization", format!("Bearer {token}"))
-        .send()
-        .await
-        .map_err(|_| {
-            json!({"code": -32603, "message": "Error making request"}).into_handler_error()
-        })?;
+/// where a transaction or output is in the chain
+fn block_text(status: &TxStatus) -> String {
+    match status.block_height {
+        Some(height) if status.confirmed => format!("confirmed in block {height}"),
+        _ => "unconfirmed".to_string(),
+    }
+}
 
-    let status = resp.status();
-    if !status.is_success() {
-        if status == StatusCode::from_u16(401).unwrap() {
-            return login(LoginRequest {}).await;
-        }
+fn lightning_success_text(payment_hash: &str, summary: &str) -> String {
+    format!("Payment success! Preimage: {payment_hash}\n{summary}")
+}
+
+fn offer_success_text(payment_hash: &str, decoded: &DecodedOffer) -> String {
+    format!(
+        "Payment success! Payment hash: {payment_hash}\n{}",
+        decoded.summary()
+    )
+}
 
-        let text = resp.text().await.map_err(|_| {
-            json!({"code": -32603, "message": "Error decoding text"}).into_handler_error()
-        })?;
+fn onchain_success_text(txid: &str, address: &ValidatedAddress) -> String {
+    format!(
+        "Payment success! Transaction id: {txid}\nAddress: {} ({})",
+        address.address, address.address_type
+    )
+}
 
-        return Err(
-            json!({"code": -32603, "message": format!("Error ({status}): {text}")})
-                .into_handler_error(),
-        );
+fn channel_success_text(txid: &str, channel: &ChannelRequest) -> String {
+    format!(
+        "Channel opening! Funding transaction id: {txid}\n{}",
+        channel.summary()
+    )
+}
+
+/// the result of the original payout, for a retry with the same idempotency key
+fn replayed_result(payout: Payout, text: String) -> CallToolResult {
+    let text = format!(
+        "{text}\nAlready paid at {} with this idempotency key, no new payout was made",
+        payout.timestamp.to_rfc3339()
+    );
+    CallToolResult::text(text)
+}
+
+fn idempotency_key_property() -> ToolInputSchemaProperty {
+    ToolInputSchemaProperty {
+        type_name: Some("string".to_owned()),
+        description: Some(
+            "Unique key for this payment, retrying with the same key returns the original result instead of paying again"
+                .to_owned(),
+        ),
+        enum_values: None,
     }
+}
 
-    let res: OnChainResponse = resp.json().await.map_err(|_| {
-        json!({"code": -32603, "message": "Error decoding response"}).into_handler_error()
-    })?;
+fn dry_run_property() -> ToolInputSchemaProperty {
+    ToolInputSchemaProperty {
+        type_name: Some("boolean".to_owned()),
+        description: Some(
+            "Validate the payment and return what would be sent to the faucet without paying"
+                .to_owned(),
+        ),
+        enum_values: None,
+    }
+}
+
+/// describe the faucet request a payout would have made, it passed validation and the spend limits
+fn dry_run_result(session: &Session, url: Url, body: Value, details: String) -> CallToolResult {
+    session.log(
+        LoggingLevel::Info,
+        "faucet",
+        json!({ "message": "Dry run, skipped faucet request", "url": url.as_str(), "body": body }),
+    );
+    let logged_in = match utilities::get_bearer_token() {
+        Some(_) => "yes",
+        None => "no, a real payment would start a login first",
+    };
+    let text = format!(
+        "Dry run, nothing was paid\n{details}\nWould send: POST {url} {body}\nLogged in: {logged_in}"
+    );
+    CallToolResult::text(text).with_structured_content(json!({ "outcome": "dry_run" }))
+}
+
+/// the faucet rejected our token, start a new login
+async fn relogin(
+    faucet: FaucetClient,
+    session: Session,
+    progress: ProgressReporter,
+) -> HandlerResult<CallToolResult> {
+    session.log(
+        LoggingLevel::Warning,
+        "auth",
+        json!({ "message": "Faucet rejected the saved token, logging in again" }),
+    );
+    login_required(faucet, session, progress).await
+}
+
+/// start a login instead of paying, marked so `fund` can tell nothing was paid
+async fn login_required(
+    faucet: FaucetClient,
+    session: Session,
+    progress: ProgressReporter,
+) -> HandlerResult<CallToolResult> {
+    let result = login(faucet, session, progress, LoginRequest {}).await?;
+    Ok(result.with_structured_content(json!({ "outcome": "login_required" })))
+}
+
+fn faucet_error(e: FaucetError) -> HandlerError {
+    json!({"code": -32603, "message": e.to_string()}).into_handler_error()
+}
 
-    let text = format!("Payment success! Transaction id: {}", res.txid);
-    Ok(CallToolResult {
-        is_error: false,
-        content: vec![CallToolResultContent::Text { text }],
-    })
+fn esplora_error(e: EsploraError) -> HandlerError {
+    json!({"code": -32603, "message": e.to_string()}).into_handler_error()
 }
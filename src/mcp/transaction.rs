@@ -0,0 +1,254 @@
+use crate::mcp::address::type_name;
+use bitcoin::consensus::encode::deserialize_hex;
+use bitcoin::{Address, Amount, Network, OutPoint, Transaction};
+use std::collections::HashSet;
+
+/// fee rate in sat/vB above which a transaction is assumed to be missing its change output
+pub const MAX_FEE_RATE: f64 = 1_000.0;
+
+/// An output of a decoded transaction
+#[derive(Debug)]
+pub struct DecodedOutput {
+    /// in sats
+    pub value: u64,
+    /// none for op_return and nonstandard scripts
+    pub address: Option<String>,
+    pub script_type: &'static str,
+}
+
+/// A raw transaction that passed the checks which don't need its inputs' values
+#[derive(Debug)]
+pub struct DecodedTransaction {
+    /// the transaction as hex, without whitespace
+    pub hex: String,
+    pub vsize: u64,
+    pub inputs: Vec<OutPoint>,
+    pub outputs: Vec<DecodedOutput>,
+    /// sats paid to all the outputs, checked not to exceed the bitcoin supply when decoding
+    output_value: u64,
+}
+
+impl DecodedTransaction {
+    /// sats paid to all the outputs
+    pub fn output_value(&self) -> u64 {
+        self.output_value
+    }
+
+    /// the fee paid given the total value of the inputs, the error explains why it isn't sane
+    pub fn fee(&self, input_value: u64) -> Result<u64, String> {
+        let output_value = self.output_value();
+        let Some(fee) = input_value.checked_sub(output_value) else {
+            return Err(format!(
+                "Outputs spend {output_value} sats but the inputs only have {input_value} sats"
+            ));
+        };
+        let fee_rate = self.fee_rate(fee);
+        if fee_rate > MAX_FEE_RATE {
+            return Err(format!(
+                "Fee of {fee} sats ({fee_rate:.2} sat/vB) is above the limit of {MAX_FEE_RATE} sat/vB, check the transaction has a change output"
+            ));
+        }
+        Ok(fee)
+    }
+
+    /// fee rate in sat/vB
+    pub fn fee_rate(&self, fee: u64) -> f64 {
+        fee as f64 / self.vsize as f64
+    }
+
+    /// human readable list of the outputs, one per line
+    pub fn summary(&self) -> String {
+        let outputs: Vec<String> = self
+            .outputs
+            .iter()
+            .map(|output| match &output.address {
+                Some(address) => format!(
+                    "- {} sats to {address} ({})",
+                    output.value, output.script_type
+                ),
+                None => format!("- {} sats ({})", output.value, output.script_type),
+            })
+            .collect();
+        format!("Outputs:\n{}", outputs.join("\n"))
+    }
+}
+
+/// parse a hex encoded transaction and check it is well formed, output scripts don't
+/// say which network they are for so they are shown as mutinynet addresses
+pub fn decode_transaction(hex: &str) -> Result<DecodedTransaction, String> {
+    let hex: String = hex.split_whitespace().collect();
+    let tx: Transaction =
+        deserialize_hex(&hex).map_err(|e| format!("Invalid transaction hex: {e}"))?;
+
+    if tx.input.is_empty() {
+        return Err("Transaction has no inputs".to_string());
+    }
+    if tx.output.is_empty() {
+        return Err("Transaction has no outputs".to_string());
+    }
+    let mut inputs = vec![];
+    let mut seen = HashSet::new();
+    for input in &tx.input {
+        let outpoint = input.previous_output;
+        if outpoint.is_null() {
+            return Err("Coinbase transactions can't be broadcast".to_string());
+        }
+        if !seen.insert(outpoint) {
+            return Err(format!("Transaction spends {outpoint} more than once"));
+        }
+        inputs.push(outpoint);
+    }
+
+    let mut outputs = vec![];
+    for (index, output) in tx.output.iter().enumerate() {
+        let script = &output.script_pubkey;
+        let (address, script_type) = if script.is_op_return() {
+            (None, "op_return")
+        } else if let Ok(address) = Address::from_script(script, Network::Signet) {
+            let script_type = address.address_type().map(type_name).unwrap_or("unknown");
+            (Some(address.to_string()), script_type)
+        } else {
+            (None, "nonstandard")
+        };
+        if output.value == Amount::ZERO && script_type != "op_return" {
+            return Err(format!("Output {index} pays 0 sats to a spendable script"));
+        }
+        outputs.push(DecodedOutput {
+            value: output.value.to_sat(),
+            address,
+            script_type,
+        });
+    }
+    // output values come straight from the hex, so their sum can overflow
+    let output_value = outputs
+        .iter()
+        .try_fold(Amount::ZERO, |total, output| {
+            total.checked_add(Amount::from_sat(output.value))
+        })
+        .filter(|total| *total <= Amount::MAX_MONEY)
+        .ok_or("Transaction outputs add up to more than 21 million bitcoin")?;
+
+    Ok(DecodedTransaction {
+        hex,
+        vsize: tx.vsize() as u64,
+        inputs,
+        outputs,
+        output_value: output_value.to_sat(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::consensus::encode::serialize_hex;
+    use bitcoin::hashes::Hash;
+    use bitcoin::{ScriptBuf, Sequence, TxIn, TxOut, Txid, Witness};
+    use std::str::FromStr;
+
+    const ADDRESS: &str = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+
+    fn build_tx(inputs: &[OutPoint], outputs: Vec<TxOut>) -> Transaction {
+        Transaction {
+            version: bitcoin::transaction::Version::TWO,
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: inputs
+                .iter()
+                .map(|outpoint| TxIn {
+                    previous_output: *outpoint,
+                    script_sig: ScriptBuf::new(),
+                    sequence: Sequence::MAX,
+                    witness: Witness::new(),
+                })
+                .collect(),
+            output: outputs,
+        }
+    }
+
+    fn pay(sats: u64) -> TxOut {
+        TxOut {
+            value: Amount::from_sat(sats),
+            script_pubkey: Address::from_str(ADDRESS)
+                .unwrap()
+                .assume_checked()
+                .script_pubkey(),
+        }
+    }
+
+    fn outpoint(vout: u32) -> OutPoint {
+        OutPoint::new(Txid::from_byte_array([1; 32]), vout)
+    }
+
+    #[test]
+    fn test_decode_transaction() {
+        let op_return = TxOut {
+            value: Amount::ZERO,
+            script_pubkey: ScriptBuf::new_op_return([1, 2, 3]),
+        };
+        let tx = build_tx(&[outpoint(0)], vec![pay(9_000), op_return]);
+        let hex = format!(" {} \n", serialize_hex(&tx));
+        let decoded = decode_transaction(&hex).unwrap();
+        assert_eq!(decoded.hex, serialize_hex(&tx));
+        assert_eq!(decoded.inputs, vec![outpoint(0)]);
+        assert_eq!(
+            decoded.summary(),
+            format!("Outputs:\n- 9000 sats to {ADDRESS} (P2WPKH)\n- 0 sats (op_return)")
+        );
+
+        assert_eq!(decoded.fee(10_000), Ok(1_000));
+        let err = decoded.fee(8_000).unwrap_err();
+        assert_eq!(
+            err,
+            "Outputs spend 9000 sats but the inputs only have 8000 sats"
+        );
+        let err = decoded.fee(1_000_000).unwrap_err();
+        assert!(
+            err.contains("check the transaction has a change output"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_malformed_transactions() {
+        let err = decode_transaction("not hex").unwrap_err();
+        assert!(err.starts_with("Invalid transaction hex"), "{err}");
+
+        let no_inputs = serialize_hex(&build_tx(&[], vec![pay(1_000)]));
+        // without inputs the segwit marker makes the hex ambiguous, either error is fine
+        assert!(decode_transaction(&no_inputs).is_err());
+
+        let no_outputs = serialize_hex(&build_tx(&[outpoint(0)], vec![]));
+        assert_eq!(
+            decode_transaction(&no_outputs).unwrap_err(),
+            "Transaction has no outputs"
+        );
+
+        let twice = serialize_hex(&build_tx(&[outpoint(0), outpoint(0)], vec![pay(1_000)]));
+        assert_eq!(
+            decode_transaction(&twice).unwrap_err(),
+            format!("Transaction spends {} more than once", outpoint(0))
+        );
+
+        let empty = serialize_hex(&build_tx(&[outpoint(0)], vec![pay(0)]));
+        assert_eq!(
+            decode_transaction(&empty).unwrap_err(),
+            "Output 0 pays 0 sats to a spendable script"
+        );
+
+        let too_much = serialize_hex(&build_tx(
+            &[outpoint(0)],
+            vec![pay(Amount::MAX_MONEY.to_sat()), pay(1)],
+        ));
+        assert_eq!(
+            decode_transaction(&too_much).unwrap_err(),
+            "Transaction outputs add up to more than 21 million bitcoin"
+        );
+        let overflow = serialize_hex(&build_tx(
+            &[outpoint(0)],
+            vec![pay(u64::MAX), pay(u64::MAX)],
+        ));
+        assert_eq!(
+            decode_transaction(&overflow).unwrap_err(),
+            "Transaction outputs add up to more than 21 million bitcoin"
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use regex::Regex;
+use serde_json::Value;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+
+static BEARER_TOKEN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\bbearer\s+[A-Za-z0-9._~+/=-]+").unwrap());
+static BOLT11: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\bln(?:bc|tb|sb)[0-9a-z]{20,}").unwrap());
+static BOLT12_OFFER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\blno1[02-9ac-hj-np-z]{20,}").unwrap());
+static LNURL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\blnurl1[02-9ac-hj-np-z]{20,}").unwrap());
+static LIGHTNING_ADDRESS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b[a-z0-9._+-]+@[a-z0-9-]+(?:\.[a-z0-9-]+)+(?::[0-9]+)?\b").unwrap()
+});
+static BECH32_ADDRESS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\b(?:bc|tb|bcrt)1[02-9ac-hj-np-z]{8,87}\b").unwrap());
+static BASE58_ADDRESS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b[123mn][1-9A-HJ-NP-Za-km-z]{25,34}\b").unwrap());
+
+/// Appends every json-rpc message exchanged with clients to a file, for debugging
+#[derive(Clone)]
+pub struct Transcript {
+    inner: Arc<Mutex<TranscriptFile>>,
+    redact: bool,
+}
+
+struct TranscriptFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_bytes: u64,
+}
+
+impl Transcript {
+    /// open (or create) the transcript, rotating it to `<path>.1` once it grows past `max_bytes`
+    pub fn open(path: &Path, max_bytes: u64, redact: bool) -> std::io::Result<Self> {
+        let file = open_append(path)?;
+        let size = file.metadata()?.len();
+        let inner = TranscriptFile {
+            path: path.to_path_buf(),
+            file,
+            size,
+            max_bytes,
+        };
+        Ok(Transcript {
+            inner: Arc::new(Mutex::new(inner)),
+            redact,
+        })
+    }
+
+    /// record a raw line received from the client
+    pub fn record_line(&self, line: &str) {
+        match serde_json::from_str::<Value>(line) {
+            Ok(message) => self.record(&message),
+            Err(_) if self.redact => self.write(&redact_str(line)),
+            Err(_) => self.write(line),
+        }
+    }
+
+    /// record a json-rpc message
+    pub fn record(&self, message: &Value) {
+        if self.redact {
+            let mut message = message.clone();
+            redact_value(&mut message);
+            self.write(&message.to_string());
+        } else {
+            self.write(&message.to_string());
+        }
+    }
+
+    fn write(&self, line: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        // the transcript is best effort, a full disk shouldn't take the server down with it
+        if let Err(e) = inner.write_line(line) {
+            eprintln!("Failed to write transcript {}: {e}", inner.path.display());
+        }
+    }
+}
+
+impl TranscriptFile {
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{line}")?;
+        self.size += len;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        let mut rotated = self.path.clone().into_os_string();
+        rotated.push(".1");
+        std::fs::rename(&self.path, rotated)?;
+        self.file = open_append(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> std::io::Result<File> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    OpenOptions::new().append(true).create(true).open(path)
+}
+
+fn redact_value(value: &mut Value) {
+    match value {
+        Value::String(s) => *s = redact_str(s),
+        Value::Array(values) => values.iter_mut().for_each(redact_value),
+        Value::Object(map) => map.values_mut().for_each(redact_value),
+        _ => {}
+    }
+}
+
+/// replace bearer tokens, payment destinations and bitcoin addresses in a string
+fn redact_str(s: &str) -> String {
+    let s = BEARER_TOKEN.replace_all(s, "Bearer [REDACTED]");
+    let s = BOLT11.replace_all(&s, "[REDACTED INVOICE]");
+    let s = BOLT12_OFFER.replace_all(&s, "[REDACTED OFFER]");
+    let s = LNURL.replace_all(&s, "[REDACTED LNURL]");
+    let s = LIGHTNING_ADDRESS.replace_all(&s, "[REDACTED LIGHTNING ADDRESS]");
+    let s = BECH32_ADDRESS.replace_all(&s, "[REDACTED ADDRESS]");
+    let s = BASE58_ADDRESS.replace_all(&s, "[REDACTED ADDRESS]");
+    s.into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_redact_str() {
+        assert_eq!(
+            redact_str("Authorization: Bearer abc.def-123"),
+            "Authorization: Bearer [REDACTED]"
+        );
+        assert_eq!(
+            redact_str(
+                "pay lntbs10u1pnxyzq9pp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypq please"
+            ),
+            "pay [REDACTED INVOICE] please"
+        );
+        assert_eq!(
+            redact_str("send to tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"),
+            "send to [REDACTED ADDRESS]"
+        );
+        assert_eq!(
+            redact_str("send to mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn now"),
+            "send to [REDACTED ADDRESS] now"
+        );
+        assert_eq!(
+            redact_str("Recipient: satoshi@getalby.com\nAmount: 5000 sats"),
+            "Recipient: [REDACTED LIGHTNING ADDRESS]\nAmount: 5000 sats"
+        );
+        assert_eq!(
+            redact_str(
+                "pay LNURL1DP68GURN8GHJ7UM9WFMXJCM99E3K7MF0V9CXJ0M385EKVCENXC6R2C35XVUKXEFCV5MKVV34X5EKZD3EV56NYD3HXQURZEPEXEJXXEPNXSCRVWFNV9NXZCN9XQ6XYEFHVGCXXCMYXYMNSERXFQ5FNS now"
+            ),
+            "pay [REDACTED LNURL] now"
+        );
+        assert_eq!(
+            redact_str(
+                "offer lno1pgx9getnwss8vetrw3hhyuckyypwa3eyt44h6txtxquqh7lz5djge4afgfjn7k4rgrkuag0jsd5xvxg"
+            ),
+            "offer [REDACTED OFFER]"
+        );
+        let txid = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+        assert_eq!(redact_str(txid), txid);
+    }
+
+    #[test]
+    fn test_redact_value_keeps_keys() {
+        let mut message = json!({
+            "params": {
+                "arguments": { "address": "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx", "amount": 5000 }
+            }
+        });
+        redact_value(&mut message);
+        assert_eq!(
+            message["params"]["arguments"],
+            json!({ "address": "[REDACTED ADDRESS]", "amount": 5000 })
+        );
+    }
+
+    #[test]
+    fn test_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mcp.jsonl");
+        let transcript = Transcript::open(&path, 64, false).unwrap();
+        for id in 0..4 {
+            transcript.record(&json!({ "jsonrpc": "2.0", "id": id, "result": {} }));
+        }
+
+        let current = std::fs::read_to_string(&path).unwrap();
+        let rotated = std::fs::read_to_string(dir.path().join("mcp.jsonl.1")).unwrap();
+        assert!(current.len() <= 64);
+        assert!(current.contains(r#""id":3"#));
+        assert!(rotated.contains(r#""id":2"#));
+    }
+}
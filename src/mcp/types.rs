@@ -60,6 +60,8 @@ pub struct ClientCapabilities {
     pub roots: Option<RootCapabilities>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sampling: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elicitation: Option<Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -119,7 +121,7 @@ pub struct ReadResourceRequest {
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ReadResourceResult {
-    pub content: ResourceContent,
+    pub contents: Vec<ResourceContent>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -222,6 +224,7 @@ pub struct ToolInputSchemaProperty {
     pub description: Option<String>,
 }
 
+#[allow(dead_code)]
 #[derive(Deserialize, Serialize, RpcParams)]
 pub struct CallToolRequest {
     pub params: ToolCallRequestParams,
@@ -234,6 +237,8 @@ pub struct ToolCallRequestParams {
     pub name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub arguments: Option<Value>,
+    #[serde(rename = "_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<MetaParams>,
 }
 
 #[derive(Deserialize, Serialize, RpcParams)]
@@ -241,6 +246,34 @@ pub struct ToolCallRequestParams {
 pub struct CallToolResult {
     pub content: Vec<CallToolResultContent>,
     pub is_error: bool,
+    /// the result as json, for clients that want to use it without parsing the text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub structured_content: Option<Value>,
+}
+
+impl CallToolResult {
+    /// a successful result with a single text block
+    pub fn text(text: impl Into<String>) -> Self {
+        CallToolResult {
+            content: vec![CallToolResultContent::Text { text: text.into() }],
+            is_error: false,
+            structured_content: None,
+        }
+    }
+
+    /// a failed tool call, the text explains what went wrong
+    pub fn error(text: impl Into<String>) -> Self {
+        CallToolResult {
+            is_error: true,
+            ..CallToolResult::text(text)
+        }
+    }
+
+    /// attach the result as json as well
+    pub fn with_structured_content(mut self, value: Value) -> Self {
+        self.structured_content = Some(value);
+        self
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -277,35 +310,52 @@ pub struct PingRequest {}
 #[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CancelledNotification {
-    pub request_id: String,
+    pub request_id: Value,
     pub reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MetaParams {
-    pub progress_token: String,
+    pub progress_token: Option<Value>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Progress {
-    pub progress_token: String,
-    pub progress: i32,
-    pub total: i32,
+    pub progress_token: Value,
+    pub progress: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, RpcParams)]
 pub struct SetLevelRequest {
-    pub level: String,
+    pub level: LoggingLevel,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct LoggingResponse {}
 
+/// syslog severities, ordered from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LoggingLevel {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+    Alert,
+    Emergency,
+}
+
 #[derive(Debug, Deserialize, Serialize, RpcParams)]
 pub struct LoggingMessageNotification {
-    pub level: String,
+    pub level: LoggingLevel,
     pub logger: String,
     pub data: Value,
 }
@@ -324,6 +374,31 @@ pub struct Root {
     pub url: String,
 }
 
+// --------- elicitation -------
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ElicitRequest {
+    pub message: String,
+    pub requested_schema: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ElicitAction {
+    Accept,
+    Decline,
+    Cancel,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ElicitResult {
+    pub action: ElicitAction,
+    #[serde(default)]
+    pub content: Value,
+}
+
+#[allow(dead_code)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ErrorCode {
     // MCP SDK error codes
@@ -355,6 +430,7 @@ impl JsonRpcResponse {
     }
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Deserialize, Serialize)]
 pub struct JsonRpcNotification {
     pub jsonrpc: String,
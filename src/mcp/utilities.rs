@@ -1,13 +1,26 @@
+use crate::mcp::session::Session;
 use crate::mcp::types::*;
-use crate::mcp::{PROTOCOL_VERSION, SERVER_NAME, SERVER_VERSION};
+use crate::mcp::{PROTOCOL_VERSION, SERVER_NAME, SERVER_VERSION, SUPPORTED_PROTOCOL_VERSIONS};
 use rpc_router::HandlerResult;
 use serde_json::{Value, json};
 use std::path::PathBuf;
+use url::Url;
 
 /// handler for `initialize` request from client
-pub async fn initialize(_request: InitializeRequest) -> HandlerResult<InitializeResult> {
+pub async fn initialize(
+    session: Session,
+    request: InitializeRequest,
+) -> HandlerResult<InitializeResult> {
+    session.set_client_capabilities(request.capabilities);
+    // answer with the client's version if we speak it, otherwise our own
+    let protocol_version =
+        if SUPPORTED_PROTOCOL_VERSIONS.contains(&request.protocol_version.as_str()) {
+            request.protocol_version
+        } else {
+            PROTOCOL_VERSION.to_string()
+        };
     let result = InitializeResult {
-        protocol_version: PROTOCOL_VERSION.to_string(),
+        protocol_version,
         server_info: Implementation {
             name: SERVER_NAME.to_string(),
             version: SERVER_VERSION.to_string(),
@@ -15,11 +28,11 @@ pub async fn initialize(_request: InitializeRequest) -> HandlerResult<Initialize
         capabilities: ServerCapabilities {
             experimental: None,
             prompts: Some(PromptCapabilities::default()),
-            resources: None,
+            resources: Some(ResourceCapabilities::default()),
             tools: Some(json!({})),
             roots: None,
             sampling: None,
-            logging: None,
+            logging: Some(json!({})),
         },
         instructions: None,
     };
@@ -35,15 +48,19 @@ pub fn graceful_shutdown() {
 pub fn notifications_initialized() {}
 
 /// handler for `notifications/cancelled` from client
-pub fn notifications_cancelled(_params: CancelledNotification) {
-    // cancel request
+pub fn notifications_cancelled(session: &Session, params: CancelledNotification) {
+    session.cancel_request(&params.request_id);
 }
 
 pub async fn ping(_request: PingRequest) -> HandlerResult<EmptyResult> {
     Ok(EmptyResult {})
 }
 
-pub async fn logging_set_level(_request: SetLevelRequest) -> HandlerResult<LoggingResponse> {
+pub async fn logging_set_level(
+    session: Session,
+    request: SetLevelRequest,
+) -> HandlerResult<LoggingResponse> {
+    session.set_log_level(request.level);
     Ok(LoggingResponse {})
 }
 
@@ -58,13 +75,13 @@ pub async fn roots_list(_request: Option<ListRootsRequest>) -> HandlerResult<Lis
 }
 
 /// send notification to client
-pub fn notify(method: &str, params: Option<Value>) {
+pub fn notify(session: &Session, method: &str, params: Option<Value>) {
     let notification = json!({
         "jsonrpc": "2.0",
         "method": method,
         "params": params,
     });
-    println!("{}", serde_json::to_string(&notification).unwrap());
+    session.send(notification);
 }
 
 pub fn get_bearer_token_location() -> PathBuf {
@@ -90,3 +107,32 @@ pub fn write_bearer_token(bearer_token: String) {
     }
     std::fs::write(loc, bearer_token.as_bytes()).unwrap();
 }
+
+/// join a path onto a base url, keeping any path prefix the base url already has
+pub fn join_url(base: &Url, path: &str) -> Url {
+    let mut url = base.clone();
+    if !url.path().ends_with('/') {
+        url.set_path(&format!("{}/", url.path()));
+    }
+    url.join(path).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_join_url_keeps_base_path() {
+        let base = Url::parse("https://faucet.mutinynet.com").unwrap();
+        assert_eq!(
+            join_url(&base, "api/onchain").as_str(),
+            "https://faucet.mutinynet.com/api/onchain"
+        );
+
+        let base = Url::parse("http://localhost:8080/faucet").unwrap();
+        assert_eq!(
+            join_url(&base, "api/lightning").as_str(),
+            "http://localhost:8080/faucet/api/lightning"
+        );
+    }
+}
@@ -0,0 +1,347 @@
+mod common;
+
+use common::*;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_transaction_status() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+    client
+        .call_tool_result(
+            "pay_mutinynet_address",
+            json!({ "address": ADDRESS, "amount": 10_000 }),
+        )
+        .await;
+
+    let result = client
+        .call_tool_result("get_transaction_status", json!({ "txid": TXID }))
+        .await;
+    assert_eq!(result["isError"], json!(false));
+    assert_eq!(
+        result_text(&result),
+        format!(
+            "Transaction {TXID}\nStatus: unconfirmed, waiting in the mempool\nFee: 141 sats (1.00 sat/vB)\nOutputs:\n- 10000 sats to {ADDRESS} (v0_p2wpkh)\n- 0 sats (op_return)"
+        )
+    );
+
+    let height = faucet.mine_block(&[TXID]);
+    faucet.mine_block(&[]);
+    let result = client
+        .call_tool_result("get_transaction_status", json!({ "txid": TXID }))
+        .await;
+    assert!(
+        result_text(&result).contains(&format!(
+            "Status: confirmed (2 confirmations) in block {height} ({height:064x})"
+        )),
+        "{result}"
+    );
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_transaction_status_not_found() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+
+    let result = client
+        .call_tool_result("get_transaction_status", json!({ "txid": TXID }))
+        .await;
+    assert_eq!(result["isError"], json!(true));
+    assert_eq!(
+        result_text(&result),
+        format!("Transaction {TXID} not found")
+    );
+
+    let result = client
+        .call_tool_result("get_transaction_status", json!({ "txid": "abc" }))
+        .await;
+    assert_eq!(result["isError"], json!(true));
+    assert_eq!(result_text(&result), "Invalid transaction id abc");
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pay_address_waits_for_confirmations() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    let id = client
+        .start_request(
+            "tools/call",
+            json!({
+                "name": "pay_mutinynet_address",
+                "arguments": { "address": ADDRESS, "wait_confirmations": 2 },
+                "_meta": { "progressToken": "pay" }
+            }),
+        )
+        .await;
+    faucet.wait_for_transaction(TXID).await;
+    let height = faucet.mine_block(&[TXID]);
+    faucet.mine_block(&[]);
+
+    let response = client.response(&id).await;
+    assert_eq!(response["result"]["isError"], json!(false));
+    let text = result_text(&response["result"]);
+    assert!(text.contains(TXID), "{text}");
+    assert!(
+        text.ends_with(&format!(
+            "Confirmed in block {height} ({height:064x}), 2 confirmations"
+        )),
+        "{text}"
+    );
+
+    let progress: Vec<(u64, u64)> = client
+        .notifications
+        .iter()
+        .filter(|n| n["method"] == "notifications/progress")
+        .map(|n| {
+            let params = &n["params"];
+            (
+                params["progress"].as_u64().unwrap(),
+                params["total"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(progress.first(), Some(&(0, 4)));
+    assert_eq!(progress.last(), Some(&(4, 4)));
+    assert!(progress.windows(2).all(|w| w[0].0 < w[1].0), "{progress:?}");
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pay_address_confirmation_timeout() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    let result = client
+        .call_tool_result(
+            "pay_mutinynet_address",
+            json!({ "address": ADDRESS, "wait_confirmations": 1, "wait_timeout_secs": 1 }),
+        )
+        .await;
+    // the payout was still made, so only the wait failed
+    assert_eq!(result["isError"], json!(false));
+    let text = result_text(&result);
+    assert!(text.contains(TXID), "{text}");
+    assert!(
+        text.ends_with(
+            "The payment was made, only the wait timed out after 1 seconds for 1 confirmations, the transaction has 0 so far"
+        ),
+        "{text}"
+    );
+    assert_eq!(faucet.requests_to("/api/onchain").len(), 1);
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pay_address_wait_timeout_too_long() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    let result = client
+        .call_tool_result(
+            "pay_mutinynet_address",
+            json!({ "address": ADDRESS, "wait_confirmations": 1, "wait_timeout_secs": u64::MAX }),
+        )
+        .await;
+    assert_eq!(result["isError"], json!(true));
+    assert_eq!(
+        result_text(&result),
+        format!(
+            "wait_timeout_secs of {} is above the maximum of 86400 seconds",
+            u64::MAX
+        )
+    );
+    assert!(faucet.requests_to("/api/onchain").is_empty());
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_address_info() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    let other_txid = "11".repeat(32);
+    let spend_txid = "22".repeat(32);
+    faucet.add_transaction(TXID, ADDRESS, 10_000);
+    faucet.add_transaction(&other_txid, ADDRESS, 3_000);
+    let height = faucet.mine_block(&[TXID, &other_txid]);
+    faucet.spend_output(&spend_txid, &other_txid, 0, REJECTED_ADDRESS, 2_500);
+
+    let result = client
+        .call_tool_result(
+            "get_address_info",
+            json!({ "address": ADDRESS.to_uppercase() }),
+        )
+        .await;
+    assert_eq!(result["isError"], json!(false));
+    assert_eq!(
+        result_text(&result),
+        format!(
+            "Address {ADDRESS} (P2WPKH)\nConfirmed balance: 13000 sats\nUnconfirmed balance: -3000 sats\nUTXOs:\n- {TXID}:0 10000 sats, confirmed in block {height}\nRecent transactions (3 of 3):\n- {spend_txid} -3000 sats, unconfirmed\n- {other_txid} +3000 sats, confirmed in block {height}\n- {TXID} +10000 sats, confirmed in block {height}"
+        )
+    );
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_address_info_empty_and_invalid() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+
+    let result = client
+        .call_tool_result("get_address_info", json!({ "address": ADDRESS }))
+        .await;
+    assert_eq!(result["isError"], json!(false));
+    assert_eq!(
+        result_text(&result),
+        format!(
+            "Address {ADDRESS} (P2WPKH)\nConfirmed balance: 0 sats\nUnconfirmed balance: +0 sats\nUTXOs: none\nRecent transactions: none"
+        )
+    );
+
+    let mainnet = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+    let result = client
+        .call_tool_result("get_address_info", json!({ "address": mainnet }))
+        .await;
+    assert_eq!(result["isError"], json!(true));
+    assert!(
+        result_text(&result).contains("is for bitcoin mainnet"),
+        "{result}"
+    );
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_chain_tip() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    faucet.add_transaction(TXID, ADDRESS, 10_000);
+    let height = faucet.mine_block(&[]);
+
+    let result = client.call_tool_result("get_chain_tip", json!({})).await;
+    assert_eq!(result["isError"], json!(false));
+    assert_eq!(
+        result_text(&result),
+        format!(
+            "Chain tip: block {height} ({height:064x})\nMined at: 2023-11-14T22:15:01+00:00\nMempool: 1 transactions, 141 vB, 141 sats in fees"
+        )
+    );
+    let expected = json!({
+        "height": height,
+        "hash": format!("{height:064x}"),
+        "timestamp": "2023-11-14T22:15:01Z",
+        "mempool": { "count": 1, "vsize": 141, "total_fee": TX_FEE },
+    });
+    assert_eq!(result["structuredContent"], expected);
+
+    let response = client
+        .request("resources/read", json!({ "uri": "mutinynet://chain/tip" }))
+        .await;
+    let content = &response["result"]["contents"][0];
+    assert_eq!(content["mimeType"], json!("application/json"));
+    let tip: serde_json::Value = serde_json::from_str(content["text"].as_str().unwrap()).unwrap();
+    assert_eq!(tip, expected);
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_fee_estimates() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+
+    let result = client
+        .call_tool_result("get_fee_estimates", json!({}))
+        .await;
+    assert_eq!(result["isError"], json!(false));
+    assert_eq!(
+        result_text(&result),
+        "Fee estimates (sat/vB):\n- 1 block: 2.50\n- 6 blocks: 1.25\n- 144 blocks: 1.00"
+    );
+    let estimates: Vec<_> = FEE_ESTIMATES
+        .iter()
+        .map(|(target, rate)| json!({ "target_blocks": target, "sat_per_vbyte": rate }))
+        .collect();
+    assert_eq!(
+        result["structuredContent"],
+        json!({ "estimates": estimates })
+    );
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_broadcast_transaction() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    faucet.add_transaction(TXID, ADDRESS, 10_000);
+    let hex = build_transaction(&[(TXID, 0)], &[(REJECTED_ADDRESS, 9_000)]);
+
+    let result = client
+        .call_tool_result("broadcast_transaction", json!({ "hex": hex }))
+        .await;
+    assert_eq!(result["isError"], json!(false), "{result}");
+    let text = result_text(&result);
+    let txid = text
+        .lines()
+        .next()
+        .unwrap()
+        .strip_prefix("Broadcast transaction ")
+        .unwrap()
+        .to_string();
+    assert_eq!(
+        text,
+        format!(
+            "Broadcast transaction {txid}\nFee: 1000 sats (10.64 sat/vB)\nOutputs:\n- 9000 sats to {REJECTED_ADDRESS} (P2TR)"
+        )
+    );
+    let result = client
+        .call_tool_result("get_transaction_status", json!({ "txid": txid }))
+        .await;
+    assert!(result_text(&result).contains("unconfirmed"), "{result}");
+
+    // esplora refuses to spend the same output twice
+    let hex = build_transaction(&[(TXID, 0)], &[(ADDRESS, 8_000)]);
+    let result = client
+        .call_tool_result("broadcast_transaction", json!({ "hex": hex }))
+        .await;
+    assert_eq!(result["isError"], json!(true));
+    assert!(
+        result_text(&result).starts_with("Transaction was rejected: sendrawtransaction RPC error"),
+        "{result}"
+    );
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_broadcast_transaction_checks_inputs() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    faucet.add_transaction(TXID, ADDRESS, 10_000);
+
+    for (hex, error) in [
+        (
+            "deadbeef".to_string(),
+            "Invalid transaction hex".to_string(),
+        ),
+        (
+            build_transaction(&[(TXID, 5)], &[(ADDRESS, 1_000)]),
+            format!("Input {TXID}:5 spends an output that doesn't exist on mutinynet"),
+        ),
+        (
+            build_transaction(&[(TXID, 0)], &[(ADDRESS, 11_000)]),
+            "Outputs spend 11000 sats but the inputs only have 10000 sats".to_string(),
+        ),
+    ] {
+        let result = client
+            .call_tool_result("broadcast_transaction", json!({ "hex": hex }))
+            .await;
+        assert_eq!(result["isError"], json!(true));
+        assert!(result_text(&result).starts_with(&error), "{result}");
+    }
+    assert!(faucet.requests_to("/esplora/tx").is_empty());
+    client.shutdown().await;
+}
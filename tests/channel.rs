@@ -0,0 +1,197 @@
+mod common;
+
+use common::*;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_open_channel_success() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    let result = client
+        .call_tool_result(
+            "open_channel",
+            json!({ "node": NODE, "capacity": 100_000, "push_amount": 10_000 }),
+        )
+        .await;
+    assert_eq!(result["isError"], json!(false));
+    assert_eq!(
+        result_text(&result),
+        format!(
+            "Channel opening! Funding transaction id: {FUNDING_TXID}\nNode: {NODE}\nCapacity: 100000 sats\nPush amount: 10000 sats"
+        )
+    );
+    let (pubkey, host) = NODE.split_once('@').unwrap();
+    assert_eq!(
+        faucet.requests_to("/api/channel"),
+        vec![json!({
+            "capacity": 100_000,
+            "push_amount": 10_000,
+            "pubkey": pubkey,
+            "host": host,
+        })]
+    );
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_open_channel_rejected_locally() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+    let (pubkey, _) = NODE.split_once('@').unwrap();
+
+    for (args, expected) in [
+        (
+            json!({ "node": pubkey, "capacity": 100_000 }),
+            "missing its address",
+        ),
+        (
+            json!({ "node": format!("{pubkey}@localhost"), "capacity": 100_000 }),
+            "Invalid node address localhost",
+        ),
+        (
+            json!({ "node": "02abcd@127.0.0.1:9735", "capacity": 100_000 }),
+            "Invalid node pubkey",
+        ),
+        (
+            json!({ "node": NODE, "capacity": 1_000 }),
+            "must be between 20000 and 16777215 sats",
+        ),
+        (
+            json!({ "node": NODE, "capacity": 100_000, "push_amount": 200_000 }),
+            "must be below the channel capacity",
+        ),
+    ] {
+        let result = client.call_tool_result("open_channel", args).await;
+        assert_eq!(result["isError"], json!(true));
+        assert!(result_text(&result).contains(expected), "{result}");
+    }
+    assert!(faucet.requests_to("/api/channel").is_empty());
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_open_channel_unauthorized_relogin() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token("expired-token");
+
+    let result = client
+        .call_tool_result("open_channel", json!({ "node": NODE, "capacity": 100_000 }))
+        .await;
+    assert!(result_text(&result).contains(USER_CODE));
+    assert_eq!(faucet.requests_to("/api/channel").len(), 1);
+    assert_eq!(faucet.requests_to("/login/device/code").len(), 1);
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_open_channel_dry_run() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    let result = client
+        .call_tool_result(
+            "open_channel",
+            json!({ "node": NODE, "capacity": 100_000, "dry_run": true }),
+        )
+        .await;
+    assert_eq!(result["isError"], json!(false));
+    let text = result_text(&result);
+    assert!(text.starts_with("Dry run, nothing was paid"), "{text}");
+    assert!(text.contains("/api/channel"), "{text}");
+    assert!(faucet.requests_to("/api/channel").is_empty());
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_open_channel_capacity_limited() {
+    let faucet = MockFaucet::start().await;
+    let mut client =
+        McpClient::spawn_with_config(&faucet, "[limits.channel]\nper_session = 150000\n").await;
+    client.save_token(VALID_TOKEN);
+
+    // the capacity is limited even when nothing is pushed
+    let open = json!({ "node": NODE, "capacity": 100_000 });
+    let result = client.call_tool_result("open_channel", open.clone()).await;
+    assert_eq!(result["isError"], json!(false));
+    let result = client.call_tool_result("open_channel", open).await;
+    assert_eq!(result["isError"], json!(true));
+    assert_eq!(
+        result_text(&result),
+        "Payout of 100000 sats is above the channel limit of 150000 sats per session, 50000 sats remaining"
+    );
+    assert_eq!(faucet.requests_to("/api/channel").len(), 1);
+
+    let result = client
+        .call_tool_result(
+            "open_channel",
+            json!({ "node": NODE, "capacity": 2_000_000 }),
+        )
+        .await;
+    assert_eq!(result["isError"], json!(true));
+    assert_eq!(
+        result_text(&result),
+        "Payout of 2000000 sats is above the channel limit of 1000000 sats per payout"
+    );
+    assert_eq!(faucet.requests_to("/api/channel").len(), 1);
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_open_channel_recorded_and_idempotent() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    let open = json!({
+        "node": NODE,
+        "capacity": 100_000,
+        "push_amount": 10_000,
+        "idempotency_key": "channel-1",
+    });
+    let first = client.call_tool_result("open_channel", open.clone()).await;
+    assert_eq!(first["isError"], json!(false));
+    let retry = client.call_tool_result("open_channel", open).await;
+    assert_eq!(retry["isError"], json!(false));
+    let text = result_text(&retry);
+    assert!(text.starts_with(result_text(&first)), "{text}");
+    assert!(text.contains("no new payout was made"), "{text}");
+    assert_eq!(faucet.requests_to("/api/channel").len(), 1);
+
+    // the same key for a channel of another size or push amount isn't a retry
+    for (capacity, push_amount) in [(200_000, 10_000), (100_000, 20_000)] {
+        let result = client
+            .call_tool_result(
+                "open_channel",
+                json!({
+                    "node": NODE,
+                    "capacity": capacity,
+                    "push_amount": push_amount,
+                    "idempotency_key": "channel-1",
+                }),
+            )
+            .await;
+        assert_eq!(result["isError"], json!(true), "{result}");
+        assert!(
+            result_text(&result).starts_with("Idempotency key channel-1 was already used"),
+            "{result}"
+        );
+    }
+    assert_eq!(faucet.requests_to("/api/channel").len(), 1);
+
+    let result = client
+        .call_tool_result("list_payouts", json!({ "kind": "channel" }))
+        .await;
+    let payouts: serde_json::Value = serde_json::from_str(result_text(&result)).unwrap();
+    assert_eq!(payouts.as_array().unwrap().len(), 1);
+    assert_eq!(payouts[0]["kind"], json!("channel"));
+    assert_eq!(payouts[0]["destination"], json!(NODE));
+    assert_eq!(payouts[0]["sats"], json!(100_000));
+    assert_eq!(payouts[0]["push_amount"], json!(10_000));
+    assert_eq!(payouts[0]["txid"], json!(FUNDING_TXID));
+    client.shutdown().await;
+}
@@ -0,0 +1,1059 @@
+#![allow(dead_code)]
+
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use bitcoin::consensus::encode::{deserialize_hex, serialize_hex};
+use bitcoin::hashes::{Hash, sha256};
+use bitcoin::secp256k1::{Secp256k1, SecretKey};
+use lightning::offers::offer::{Offer, OfferBuilder};
+use lightning_invoice::{
+    Bolt11Invoice, Bolt11InvoiceDescriptionRef, Currency, InvoiceBuilder, PaymentSecret,
+};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::TcpListener;
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+
+pub const VALID_TOKEN: &str = "valid-faucet-token";
+pub const PAYMENT_HASH: &str = "7c0b0a1e1f8f7bd2b4f9bbbd2b7c2b3ad9fb8b8f09e0bbd1d5a8b5b2e3e0c1a2";
+pub const TXID: &str = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+/// invoices with this as their description are rejected by the mock faucet
+pub const REJECTED_MARKER: &str = "reject";
+/// a valid signet address
+pub const ADDRESS: &str = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+/// a valid signet address that the mock faucet refuses to pay
+pub const REJECTED_ADDRESS: &str = "tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c";
+
+/// metadata of every pay request served by the LNURL stand-in
+pub const LNURL_METADATA: &str = r#"[["text/plain","Mutinynet test wallet"]]"#;
+pub const LNURL_MIN_SATS: u64 = 1_000;
+pub const LNURL_MAX_SATS: u64 = 100_000;
+/// the LNURL stand-in answers with an error for this user
+pub const LNURL_UNKNOWN_USER: &str = "unknown";
+/// the LNURL stand-in returns invoices that don't commit to its metadata for this user
+pub const LNURL_WRONG_HASH_USER: &str = "wronghash";
+/// the LNURL stand-in returns invoices for one sat more than asked for this user
+pub const LNURL_WRONG_AMOUNT_USER: &str = "wrongamount";
+
+/// a lightning node to open channels to
+pub const NODE: &str =
+    "02eec7245d6b7d2ccb30380bfbe2a3648cd7a942653f5aa340edcea1f283686619@127.0.0.1:9735";
+/// txid of every channel funding transaction made by the mock faucet
+pub const FUNDING_TXID: &str = "a2c1e0e3b2b5a8d5d1bb0e09f8b8fbd93a2b7c2bbdbbf9b4d27b8f1f1e0a0b7c";
+
+/// fee of every transaction served by the esplora stand-in
+pub const TX_FEE: u64 = 141;
+/// weight of every transaction served by the esplora stand-in, 141 vbytes
+pub const TX_WEIGHT: u64 = 561;
+const START_HEIGHT: u64 = 100;
+/// block times of the esplora stand-in are this plus the height
+pub const GENESIS_TIME: u64 = 1_700_000_000;
+/// confirmation targets and fee rates served by the esplora stand-in
+pub const FEE_ESTIMATES: [(u16, f64); 3] = [(1, 2.5), (6, 1.25), (144, 1.0)];
+
+const GH_ACCESS_TOKEN: &str = "gh-access-token";
+const DEVICE_CODE: &str = "device-code";
+pub const USER_CODE: &str = "ABCD-1234";
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
+pub struct MockState {
+    /// whether the user has entered the device code on the github stand-in
+    pub authorized: bool,
+    /// every request received, as (path, body)
+    pub requests: Vec<(String, Value)>,
+    /// transactions known to the esplora stand-in, by txid
+    pub transactions: HashMap<String, Value>,
+    pub tip_height: u64,
+    /// how long the faucet takes to answer an on-chain payout it has accepted
+    pub payout_delay: Duration,
+}
+
+/// Local stand-in for the mutinynet faucet, github's device flow and esplora,
+/// which is served under `/esplora`
+#[derive(Clone)]
+pub struct MockFaucet {
+    pub url: String,
+    pub state: Arc<Mutex<MockState>>,
+}
+
+impl MockFaucet {
+    pub async fn start() -> Self {
+        let state = Arc::new(Mutex::new(MockState {
+            tip_height: START_HEIGHT,
+            ..Default::default()
+        }));
+        let app = Router::new()
+            .route("/api/lightning", post(lightning))
+            .route("/api/bolt12", post(bolt12))
+            .route("/.well-known/lnurlp/{user}", get(lnurl_pay_request))
+            .route("/lnurlp/{user}/callback", get(lnurl_callback))
+            .route("/api/onchain", post(onchain))
+            .route("/api/channel", post(channel))
+            .route("/auth/github/device", post(github_device))
+            .route("/login/device/code", post(device_code))
+            .route("/login/oauth/access_token", post(access_token))
+            .route("/esplora/tx", post(esplora_broadcast))
+            .route("/esplora/tx/{txid}", get(esplora_tx))
+            .route("/esplora/blocks/tip/height", get(esplora_tip_height))
+            .route("/esplora/blocks/tip/hash", get(esplora_tip_hash))
+            .route("/esplora/block/{hash}", get(esplora_block))
+            .route("/esplora/mempool", get(esplora_mempool))
+            .route("/esplora/fee-estimates", get(esplora_fee_estimates))
+            .route("/esplora/address/{address}", get(esplora_address))
+            .route(
+                "/esplora/address/{address}/utxo",
+                get(esplora_address_utxos),
+            )
+            .route("/esplora/address/{address}/txs", get(esplora_address_txs))
+            .with_state(state.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        MockFaucet { url, state }
+    }
+
+    /// simulate the user entering the device code on github
+    pub fn authorize_device(&self) {
+        self.state.lock().unwrap().authorized = true;
+    }
+
+    pub fn esplora_url(&self) -> String {
+        format!("{}/esplora", self.url)
+    }
+
+    /// lightning address of a user of the LNURL stand-in
+    pub fn lightning_address(&self, user: &str) -> String {
+        format!("{user}@{}", self.url.strip_prefix("http://").unwrap())
+    }
+
+    /// put an unconfirmed transaction paying `sats` to `address` in the esplora mempool
+    pub fn add_transaction(&self, txid: &str, address: &str, sats: u64) {
+        insert_transaction(&self.state, txid, address, sats);
+    }
+
+    /// put an unconfirmed transaction in the esplora mempool spending output `vout` of
+    /// `spent_txid` and paying `sats` to `address`
+    pub fn spend_output(
+        &self,
+        txid: &str,
+        spent_txid: &str,
+        vout: usize,
+        address: &str,
+        sats: u64,
+    ) {
+        let prevout = self.state.lock().unwrap().transactions[spent_txid]["vout"][vout].clone();
+        insert_transaction(&self.state, txid, address, sats);
+        self.state
+            .lock()
+            .unwrap()
+            .transactions
+            .get_mut(txid)
+            .unwrap()["vin"] = json!([{
+            "txid": spent_txid,
+            "vout": vout,
+            "prevout": prevout,
+        }]);
+    }
+
+    /// wait until the esplora stand-in knows about a transaction, e.g. a payout being made
+    pub async fn wait_for_transaction(&self, txid: &str) {
+        tokio::time::timeout(TIMEOUT, async {
+            while !self.state.lock().unwrap().transactions.contains_key(txid) {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("timed out waiting for transaction");
+    }
+
+    /// mine a block on the esplora stand-in, confirming the given transactions in it
+    pub fn mine_block(&self, txids: &[&str]) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        state.tip_height += 1;
+        let height = state.tip_height;
+        for txid in txids {
+            let tx = state.transactions.get_mut(*txid).unwrap();
+            tx["status"] = json!({
+                "confirmed": true,
+                "block_height": height,
+                "block_hash": format!("{height:064x}"),
+                "block_time": GENESIS_TIME + height,
+            });
+        }
+        height
+    }
+
+    /// requests made to the given path
+    pub fn requests_to(&self, path: &str) -> Vec<Value> {
+        self.state
+            .lock()
+            .unwrap()
+            .requests
+            .iter()
+            .filter(|(p, _)| p == path)
+            .map(|(_, body)| body.clone())
+            .collect()
+    }
+
+    fn record(state: &Mutex<MockState>, path: &str, body: &Value) {
+        state
+            .lock()
+            .unwrap()
+            .requests
+            .push((path.to_string(), body.clone()));
+    }
+}
+
+fn is_authorized(headers: &HeaderMap) -> bool {
+    headers
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v == format!("Bearer {VALID_TOKEN}"))
+}
+
+async fn lightning(
+    State(state): State<Arc<Mutex<MockState>>>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    MockFaucet::record(&state, "/api/lightning", &body);
+    if !is_authorized(&headers) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    let bolt11 = body["bolt11"].as_str().unwrap_or_default();
+    let Ok(invoice) = Bolt11Invoice::from_str(bolt11) else {
+        return (StatusCode::BAD_REQUEST, "Invalid invoice").into_response();
+    };
+    if matches!(invoice.description(), Bolt11InvoiceDescriptionRef::Direct(d) if d.to_string() == REJECTED_MARKER)
+    {
+        return (StatusCode::BAD_REQUEST, "Invalid invoice").into_response();
+    }
+    Json(json!({ "payment_hash": PAYMENT_HASH })).into_response()
+}
+
+async fn bolt12(
+    State(state): State<Arc<Mutex<MockState>>>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    MockFaucet::record(&state, "/api/bolt12", &body);
+    if !is_authorized(&headers) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    let offer = body["offer"].as_str().unwrap_or_default();
+    let Ok(offer) = Offer::from_str(offer) else {
+        return (StatusCode::BAD_REQUEST, "Invalid offer").into_response();
+    };
+    if offer
+        .description()
+        .is_some_and(|d| d.to_string() == REJECTED_MARKER)
+    {
+        return (StatusCode::BAD_REQUEST, "Invalid offer").into_response();
+    }
+    Json(json!({ "payment_hash": PAYMENT_HASH })).into_response()
+}
+
+async fn lnurl_pay_request(headers: HeaderMap, Path(user): Path<String>) -> Response {
+    if user == LNURL_UNKNOWN_USER {
+        return Json(json!({ "status": "ERROR", "reason": "Unknown user" })).into_response();
+    }
+    let host = headers["host"].to_str().unwrap();
+    Json(json!({
+        "tag": "payRequest",
+        "callback": format!("http://{host}/lnurlp/{user}/callback"),
+        "minSendable": LNURL_MIN_SATS * 1_000,
+        "maxSendable": LNURL_MAX_SATS * 1_000,
+        "metadata": LNURL_METADATA,
+    }))
+    .into_response()
+}
+
+async fn lnurl_callback(
+    State(state): State<Arc<Mutex<MockState>>>,
+    Path(user): Path<String>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    let amount_msats: u64 = query["amount"].parse().unwrap();
+    MockFaucet::record(
+        &state,
+        "/lnurlp/callback",
+        &json!({ "user": user, "amount": amount_msats }),
+    );
+    let pr = match user.as_str() {
+        LNURL_WRONG_HASH_USER => lnurl_invoice(amount_msats, "[]"),
+        LNURL_WRONG_AMOUNT_USER => lnurl_invoice(amount_msats + 1_000, LNURL_METADATA),
+        _ => lnurl_invoice(amount_msats, LNURL_METADATA),
+    };
+    Json(json!({ "pr": pr, "routes": [] })).into_response()
+}
+
+async fn onchain(
+    State(state): State<Arc<Mutex<MockState>>>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    MockFaucet::record(&state, "/api/onchain", &body);
+    if !is_authorized(&headers) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    let address = body["address"].as_str().unwrap_or_default();
+    if address == REJECTED_ADDRESS {
+        return (StatusCode::BAD_REQUEST, "Invalid address").into_response();
+    }
+    let sats = body["sats"].as_u64().unwrap_or_default();
+    insert_transaction(&state, TXID, address, sats);
+    let delay = state.lock().unwrap().payout_delay;
+    tokio::time::sleep(delay).await;
+    Json(json!({ "txid": TXID })).into_response()
+}
+
+async fn channel(
+    State(state): State<Arc<Mutex<MockState>>>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    MockFaucet::record(&state, "/api/channel", &body);
+    if !is_authorized(&headers) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    Json(json!({ "txid": FUNDING_TXID })).into_response()
+}
+
+async fn github_device(
+    State(state): State<Arc<Mutex<MockState>>>,
+    Json(body): Json<Value>,
+) -> Response {
+    MockFaucet::record(&state, "/auth/github/device", &body);
+    if body["code"] != GH_ACCESS_TOKEN {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    Json(json!({ "token": VALID_TOKEN })).into_response()
+}
+
+async fn device_code(
+    State(state): State<Arc<Mutex<MockState>>>,
+    Json(body): Json<Value>,
+) -> Response {
+    MockFaucet::record(&state, "/login/device/code", &body);
+    Json(json!({
+        "device_code": DEVICE_CODE,
+        "user_code": USER_CODE,
+        "verification_uri": "https://github.com/login/device",
+        "expires_in": 60,
+        "interval": 1,
+    }))
+    .into_response()
+}
+
+async fn access_token(
+    State(state): State<Arc<Mutex<MockState>>>,
+    Json(body): Json<Value>,
+) -> Response {
+    MockFaucet::record(&state, "/login/oauth/access_token", &body);
+    if body["device_code"] != DEVICE_CODE || !state.lock().unwrap().authorized {
+        return Json(json!({ "error": "authorization_pending" })).into_response();
+    }
+    Json(json!({ "access_token": GH_ACCESS_TOKEN, "token_type": "bearer" })).into_response()
+}
+
+fn insert_transaction(state: &Mutex<MockState>, txid: &str, address: &str, sats: u64) {
+    let tx = json!({
+        "txid": txid,
+        "version": 2,
+        "locktime": 0,
+        "vin": [],
+        "vout": [
+            {
+                "scriptpubkey": "",
+                "scriptpubkey_address": address,
+                "scriptpubkey_type": "v0_p2wpkh",
+                "value": sats,
+            },
+            {
+                "scriptpubkey": "6a",
+                "scriptpubkey_type": "op_return",
+                "value": 0,
+            },
+        ],
+        "size": 222,
+        "weight": TX_WEIGHT,
+        "fee": TX_FEE,
+        "status": { "confirmed": false },
+    });
+    state
+        .lock()
+        .unwrap()
+        .transactions
+        .insert(txid.to_string(), tx);
+}
+
+async fn esplora_tx(
+    State(state): State<Arc<Mutex<MockState>>>,
+    Path(txid): Path<String>,
+) -> Response {
+    match state.lock().unwrap().transactions.get(&txid) {
+        Some(tx) => Json(tx.clone()).into_response(),
+        None => (StatusCode::NOT_FOUND, "Transaction not found").into_response(),
+    }
+}
+
+async fn esplora_broadcast(State(state): State<Arc<Mutex<MockState>>>, body: String) -> Response {
+    MockFaucet::record(&state, "/esplora/tx", &json!(body));
+    let Ok(tx) = deserialize_hex::<bitcoin::Transaction>(&body) else {
+        return (StatusCode::BAD_REQUEST, "TX decode failed").into_response();
+    };
+    let mut state = state.lock().unwrap();
+    let mut vin = vec![];
+    let mut input_value = 0;
+    for input in &tx.input {
+        let txid = input.previous_output.txid.to_string();
+        let vout = input.previous_output.vout;
+        let already_spent = state.transactions.values().any(|tx| {
+            tx["vin"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|i| i["txid"] == txid && i["vout"] == vout)
+        });
+        let prevout = state
+            .transactions
+            .get(&txid)
+            .map(|tx| tx["vout"][vout as usize].clone())
+            .unwrap_or(Value::Null);
+        if already_spent || prevout.is_null() {
+            let error =
+                r#"sendrawtransaction RPC error: {"code":-26,"message":"txn-mempool-conflict"}"#;
+            return (StatusCode::BAD_REQUEST, error).into_response();
+        }
+        input_value += prevout["value"].as_u64().unwrap();
+        vin.push(json!({ "txid": txid, "vout": vout, "prevout": prevout }));
+    }
+    let vout: Vec<Value> = tx
+        .output
+        .iter()
+        .map(|out| {
+            let address =
+                bitcoin::Address::from_script(&out.script_pubkey, bitcoin::Network::Signet)
+                    .ok()
+                    .map(|address| address.to_string());
+            let script_type = if out.script_pubkey.is_op_return() {
+                "op_return"
+            } else if out.script_pubkey.is_p2wpkh() {
+                "v0_p2wpkh"
+            } else {
+                "unknown"
+            };
+            json!({
+                "scriptpubkey": out.script_pubkey.to_hex_string(),
+                "scriptpubkey_address": address,
+                "scriptpubkey_type": script_type,
+                "value": out.value.to_sat(),
+            })
+        })
+        .collect();
+    let output_value: u64 = tx.output.iter().map(|out| out.value.to_sat()).sum();
+    let txid = tx.compute_txid().to_string();
+    let json = json!({
+        "txid": txid,
+        "version": 2,
+        "locktime": 0,
+        "vin": vin,
+        "vout": vout,
+        "size": tx.total_size(),
+        "weight": tx.weight().to_wu(),
+        "fee": input_value - output_value,
+        "status": { "confirmed": false },
+    });
+    state.transactions.insert(txid.clone(), json);
+    txid.into_response()
+}
+
+async fn esplora_tip_height(State(state): State<Arc<Mutex<MockState>>>) -> Response {
+    state.lock().unwrap().tip_height.to_string().into_response()
+}
+
+async fn esplora_tip_hash(State(state): State<Arc<Mutex<MockState>>>) -> Response {
+    format!("{:064x}", state.lock().unwrap().tip_height).into_response()
+}
+
+async fn esplora_block(
+    State(state): State<Arc<Mutex<MockState>>>,
+    Path(hash): Path<String>,
+) -> Response {
+    // block hashes are the height in hex
+    match u64::from_str_radix(&hash, 16) {
+        Ok(height) if height <= state.lock().unwrap().tip_height => Json(json!({
+            "id": hash,
+            "height": height,
+            "timestamp": GENESIS_TIME + height,
+        }))
+        .into_response(),
+        _ => (StatusCode::NOT_FOUND, "Block not found").into_response(),
+    }
+}
+
+async fn esplora_mempool(State(state): State<Arc<Mutex<MockState>>>) -> Response {
+    let state = state.lock().unwrap();
+    let unconfirmed: Vec<&Value> = state
+        .transactions
+        .values()
+        .filter(|tx| tx["status"]["confirmed"] != true)
+        .collect();
+    let sum = |field: &str| -> u64 {
+        unconfirmed
+            .iter()
+            .map(|tx| tx[field].as_u64().unwrap())
+            .sum()
+    };
+    Json(json!({
+        "count": unconfirmed.len(),
+        "vsize": sum("weight").div_ceil(4),
+        "total_fee": sum("fee"),
+        "fee_histogram": [],
+    }))
+    .into_response()
+}
+
+async fn esplora_fee_estimates() -> Response {
+    let estimates: HashMap<String, f64> = FEE_ESTIMATES
+        .iter()
+        .map(|(target, rate)| (target.to_string(), *rate))
+        .collect();
+    Json(estimates).into_response()
+}
+
+/// transactions paying to or spending from an address, mempool first, then newest first
+fn address_transactions(state: &MockState, address: &str) -> Vec<Value> {
+    let mut txs: Vec<Value> = state
+        .transactions
+        .values()
+        .filter(|tx| {
+            let outputs = tx["vout"].as_array().unwrap().iter();
+            let prevouts = tx["vin"].as_array().unwrap().iter().map(|i| &i["prevout"]);
+            outputs
+                .chain(prevouts)
+                .any(|out| out["scriptpubkey_address"] == address)
+        })
+        .cloned()
+        .collect();
+    txs.sort_by_key(|tx| {
+        let height = tx["status"]["block_height"].as_u64().unwrap_or(u64::MAX);
+        (
+            std::cmp::Reverse(height),
+            tx["txid"].as_str().unwrap().to_string(),
+        )
+    });
+    txs
+}
+
+async fn esplora_address(
+    State(state): State<Arc<Mutex<MockState>>>,
+    Path(address): Path<String>,
+) -> Response {
+    let state = state.lock().unwrap();
+    let value_to_address = |outputs: Vec<&Value>| -> u64 {
+        outputs
+            .into_iter()
+            .filter(|out| out["scriptpubkey_address"] == address)
+            .map(|out| out["value"].as_u64().unwrap())
+            .sum()
+    };
+    // funded, spent and tx count, for the mempool then the chain
+    let mut stats = [[0u64; 3]; 2];
+    for tx in address_transactions(&state, &address) {
+        let stats = &mut stats[usize::from(tx["status"]["confirmed"] == true)];
+        stats[0] += value_to_address(tx["vout"].as_array().unwrap().iter().collect());
+        stats[1] += value_to_address(
+            tx["vin"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|i| &i["prevout"])
+                .collect(),
+        );
+        stats[2] += 1;
+    }
+    let [mempool_stats, chain_stats] = stats.map(|[funded, spent, tx_count]| {
+        json!({
+            "funded_txo_sum": funded,
+            "spent_txo_sum": spent,
+            "tx_count": tx_count,
+        })
+    });
+    Json(json!({
+        "address": address,
+        "chain_stats": chain_stats,
+        "mempool_stats": mempool_stats,
+    }))
+    .into_response()
+}
+
+async fn esplora_address_utxos(
+    State(state): State<Arc<Mutex<MockState>>>,
+    Path(address): Path<String>,
+) -> Response {
+    let state = state.lock().unwrap();
+    let spent: Vec<(Value, Value)> = state
+        .transactions
+        .values()
+        .flat_map(|tx| tx["vin"].as_array().unwrap().iter())
+        .map(|input| (input["txid"].clone(), input["vout"].clone()))
+        .collect();
+    let mut utxos = vec![];
+    for tx in address_transactions(&state, &address) {
+        for (vout, out) in tx["vout"].as_array().unwrap().iter().enumerate() {
+            if out["scriptpubkey_address"] == address
+                && !spent.contains(&(tx["txid"].clone(), json!(vout)))
+            {
+                utxos.push(json!({
+                    "txid": tx["txid"],
+                    "vout": vout,
+                    "value": out["value"],
+                    "status": tx["status"],
+                }));
+            }
+        }
+    }
+    Json(utxos).into_response()
+}
+
+async fn esplora_address_txs(
+    State(state): State<Arc<Mutex<MockState>>>,
+    Path(address): Path<String>,
+) -> Response {
+    Json(address_transactions(&state.lock().unwrap(), &address)).into_response()
+}
+
+/// hex of an unsigned transaction spending the given outputs to the given addresses
+pub fn build_transaction(inputs: &[(&str, u32)], outputs: &[(&str, u64)]) -> String {
+    let tx = bitcoin::Transaction {
+        version: bitcoin::transaction::Version::TWO,
+        lock_time: bitcoin::absolute::LockTime::ZERO,
+        input: inputs
+            .iter()
+            .map(|(txid, vout)| bitcoin::TxIn {
+                previous_output: bitcoin::OutPoint::new(
+                    bitcoin::Txid::from_str(txid).unwrap(),
+                    *vout,
+                ),
+                ..Default::default()
+            })
+            .collect(),
+        output: outputs
+            .iter()
+            .map(|(address, sats)| bitcoin::TxOut {
+                value: bitcoin::Amount::from_sat(*sats),
+                script_pubkey: bitcoin::Address::from_str(address)
+                    .unwrap()
+                    .assume_checked()
+                    .script_pubkey(),
+            })
+            .collect(),
+    };
+    serialize_hex(&tx)
+}
+
+/// The server binary driven over stdio, with its own home directory
+pub struct McpClient {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+    home: TempDir,
+    next_id: u64,
+    /// notifications received while waiting for responses
+    pub notifications: Vec<Value>,
+}
+
+impl McpClient {
+    pub async fn spawn(faucet: &MockFaucet) -> Self {
+        Self::spawn_with_args(faucet, &[]).await
+    }
+
+    pub async fn spawn_with_args(faucet: &MockFaucet, args: &[&str]) -> Self {
+        Self::spawn_in_home(faucet, TempDir::new().unwrap(), args, json!({})).await
+    }
+
+    /// start the server as a client that supports elicitation
+    pub async fn spawn_with_elicitation(faucet: &MockFaucet, args: &[&str]) -> Self {
+        let capabilities = json!({ "elicitation": {} });
+        Self::spawn_in_home(faucet, TempDir::new().unwrap(), args, capabilities).await
+    }
+
+    /// start the server with the given `config.toml` contents
+    pub async fn spawn_with_config(faucet: &MockFaucet, config: &str) -> Self {
+        let home = TempDir::new().unwrap();
+        let dir = home.path().join(".config/mcp/mutinynet");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("config.toml"), config).unwrap();
+        Self::spawn_in_home(faucet, home, &[], json!({})).await
+    }
+
+    async fn spawn_in_home(
+        faucet: &MockFaucet,
+        home: TempDir,
+        args: &[&str],
+        capabilities: Value,
+    ) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_mcp-mutinynet"))
+            .arg("--mcp")
+            .args(["--faucet-url", &faucet.url])
+            .args(["--github-url", &faucet.url])
+            .args(["--esplora-url", &faucet.esplora_url()])
+            .args(args)
+            .env("HOME", home.path())
+            .env_remove("MUTINYNET_FAUCET_URL")
+            .env_remove("MUTINYNET_GITHUB_URL")
+            .env_remove("MUTINYNET_ESPLORA_URL")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap()).lines();
+        let mut client = McpClient {
+            child,
+            stdin,
+            stdout,
+            home,
+            next_id: 0,
+            notifications: vec![],
+        };
+        let init = client
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": "2025-06-18",
+                    "capabilities": capabilities,
+                    "clientInfo": { "name": "test", "version": "0.0.0" }
+                }),
+            )
+            .await;
+        assert!(init.get("result").is_some(), "initialize failed: {init}");
+        client.notify("notifications/initialized", json!({})).await;
+        client
+    }
+
+    pub fn home(&self) -> PathBuf {
+        self.home.path().to_path_buf()
+    }
+
+    pub fn token_path(&self) -> PathBuf {
+        self.home().join(".config/mcp/mutinynet/gh-token")
+    }
+
+    /// pretend a previous login saved this faucet token
+    pub fn save_token(&self, token: &str) {
+        let path = self.token_path();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, token).unwrap();
+    }
+
+    pub async fn send(&mut self, message: Value) {
+        let line = format!("{message}\n");
+        self.stdin.write_all(line.as_bytes()).await.unwrap();
+        self.stdin.flush().await.unwrap();
+    }
+
+    pub async fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+            .await;
+    }
+
+    /// send a request without waiting for the response, returning its id
+    pub async fn start_request(&mut self, method: &str, params: Value) -> Value {
+        self.next_id += 1;
+        let id = json!(self.next_id);
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))
+            .await;
+        id
+    }
+
+    pub async fn request(&mut self, method: &str, params: Value) -> Value {
+        let id = self.start_request(method, params).await;
+        self.response(&id).await
+    }
+
+    /// call a tool, returning the json-rpc response
+    pub async fn call_tool(&mut self, name: &str, arguments: Value) -> Value {
+        self.request(
+            "tools/call",
+            json!({ "name": name, "arguments": arguments }),
+        )
+        .await
+    }
+
+    /// call a tool that is expected to succeed at the json-rpc level, returning its result
+    pub async fn call_tool_result(&mut self, name: &str, arguments: Value) -> Value {
+        let response = self.call_tool(name, arguments).await;
+        response
+            .get("result")
+            .cloned()
+            .unwrap_or_else(|| panic!("{name} failed: {response}"))
+    }
+
+    /// read the next message from the server
+    pub async fn recv(&mut self) -> Value {
+        let line = tokio::time::timeout(TIMEOUT, self.stdout.next_line())
+            .await
+            .expect("timed out waiting for the server")
+            .unwrap()
+            .expect("server closed stdout");
+        serde_json::from_str(&line).unwrap()
+    }
+
+    /// wait for the response to the given request, collecting notifications on the way
+    pub async fn response(&mut self, id: &Value) -> Value {
+        loop {
+            let message = self.recv().await;
+            if message.get("id") == Some(id) && message.get("method").is_none() {
+                return message;
+            }
+            if message.get("method").is_some() && message.get("id").is_none() {
+                self.notifications.push(message);
+            }
+        }
+    }
+
+    /// wait for the server to send a request, collecting notifications on the way
+    pub async fn server_request(&mut self, method: &str) -> Value {
+        loop {
+            let message = self.recv().await;
+            if message["method"] == method && message.get("id").is_some() {
+                return message;
+            }
+            if message.get("method").is_some() && message.get("id").is_none() {
+                self.notifications.push(message);
+            }
+        }
+    }
+
+    /// answer a request the server sent
+    pub async fn respond(&mut self, id: &Value, result: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+            .await;
+    }
+
+    /// wait until a file exists, e.g. the token written by a background login
+    pub async fn wait_for_file(&self, path: &std::path::Path) {
+        tokio::time::timeout(TIMEOUT, async {
+            while !path.exists() {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("timed out waiting for file");
+    }
+
+    /// close stdin and wait for the server to exit
+    pub async fn shutdown(mut self) {
+        drop(self.stdin);
+        tokio::time::timeout(TIMEOUT, self.child.wait())
+            .await
+            .expect("server did not exit")
+            .unwrap();
+    }
+}
+
+/// The server binary serving the streamable HTTP transport, with its own home directory
+pub struct HttpServer {
+    child: Child,
+    home: TempDir,
+    pub url: String,
+    pub client: reqwest::Client,
+}
+
+impl HttpServer {
+    pub async fn spawn(faucet: &MockFaucet, args: &[&str]) -> Self {
+        // find a free port for the server to bind
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let home = TempDir::new().unwrap();
+        let child = Command::new(env!("CARGO_BIN_EXE_mcp-mutinynet"))
+            .args(["--http", &addr.to_string()])
+            .args(["--faucet-url", &faucet.url])
+            .args(["--github-url", &faucet.url])
+            .args(["--esplora-url", &faucet.esplora_url()])
+            .args(args)
+            .env("HOME", home.path())
+            .env_remove("MUTINYNET_FAUCET_URL")
+            .env_remove("MUTINYNET_GITHUB_URL")
+            .env_remove("MUTINYNET_ESPLORA_URL")
+            .kill_on_drop(true)
+            .spawn()
+            .unwrap();
+        tokio::time::timeout(TIMEOUT, async {
+            while tokio::net::TcpStream::connect(addr).await.is_err() {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("timed out waiting for the server to listen");
+        HttpServer {
+            child,
+            home,
+            url: format!("http://{addr}/mcp"),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// pretend a previous login saved this faucet token
+    pub fn save_token(&self, token: &str) {
+        let path = self.home.path().join(".config/mcp/mutinynet/gh-token");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, token).unwrap();
+    }
+
+    /// start a session, returning its `Mcp-Session-Id`
+    pub async fn initialize(&self) -> String {
+        let response = self
+            .post(
+                None,
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "method": "initialize",
+                    "params": {
+                        "protocolVersion": "2025-06-18",
+                        "capabilities": {},
+                        "clientInfo": { "name": "test", "version": "0.0.0" }
+                    }
+                }),
+            )
+            .await;
+        assert_eq!(response.status(), 200);
+        let session_id = response.headers()["mcp-session-id"]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body: Value = response.json().await.unwrap();
+        assert!(body.get("result").is_some(), "initialize failed: {body}");
+        session_id
+    }
+
+    /// post a message, accepting only a json response
+    pub async fn post(&self, session_id: Option<&str>, message: Value) -> reqwest::Response {
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header("Accept", "application/json")
+            .json(&message);
+        if let Some(session_id) = session_id {
+            request = request.header("Mcp-Session-Id", session_id);
+        }
+        request.send().await.unwrap()
+    }
+
+    /// post a request accepting an SSE response, returning every message streamed on it
+    pub async fn post_stream(&self, session_id: &str, message: Value) -> Vec<Value> {
+        let response = self
+            .client
+            .post(&self.url)
+            .header("Accept", "application/json, text/event-stream")
+            .header("Mcp-Session-Id", session_id)
+            .json(&message)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        let body = tokio::time::timeout(TIMEOUT, response.text())
+            .await
+            .expect("timed out waiting for the stream to end")
+            .unwrap();
+        sse_messages(&body)
+    }
+}
+
+/// messages in the `data:` lines of an SSE body
+pub fn sse_messages(body: &str) -> Vec<Value> {
+    body.lines()
+        .filter_map(|line| line.strip_prefix("data: "))
+        .map(|data| serde_json::from_str(data).unwrap())
+        .collect()
+}
+
+/// text of the first content item of a tool result
+pub fn result_text(result: &Value) -> &str {
+    result["content"][0]["text"].as_str().unwrap()
+}
+
+/// a signed invoice for the given network
+pub fn build_invoice(
+    currency: Currency,
+    amount_msats: u64,
+    description: &str,
+    timestamp: SystemTime,
+) -> String {
+    let secp = Secp256k1::new();
+    let key = SecretKey::from_slice(&[42; 32]).unwrap();
+    InvoiceBuilder::new(currency)
+        .description(description.to_string())
+        .payment_hash(sha256::Hash::from_byte_array([1; 32]))
+        .payment_secret(PaymentSecret([2; 32]))
+        .timestamp(timestamp)
+        .expiry_time(Duration::from_secs(3600))
+        .min_final_cltv_expiry_delta(144)
+        .amount_milli_satoshis(amount_msats)
+        .build_signed(|hash| secp.sign_ecdsa_recoverable(hash, &key))
+        .unwrap()
+        .to_string()
+}
+
+/// a fresh mutinynet invoice committing to the hash of an LNURL's metadata
+pub fn lnurl_invoice(amount_msats: u64, metadata: &str) -> String {
+    let secp = Secp256k1::new();
+    let key = SecretKey::from_slice(&[42; 32]).unwrap();
+    InvoiceBuilder::new(Currency::Signet)
+        .description_hash(sha256::Hash::hash(metadata.as_bytes()))
+        .payment_hash(sha256::Hash::from_byte_array([1; 32]))
+        .payment_secret(PaymentSecret([2; 32]))
+        .current_timestamp()
+        .expiry_time(Duration::from_secs(3600))
+        .min_final_cltv_expiry_delta(144)
+        .amount_milli_satoshis(amount_msats)
+        .build_signed(|hash| secp.sign_ecdsa_recoverable(hash, &key))
+        .unwrap()
+        .to_string()
+}
+
+/// an offer from a fixed key, without an amount unless one is given
+pub fn build_offer(
+    network: bitcoin::Network,
+    amount_msats: Option<u64>,
+    description: &str,
+) -> String {
+    let secp = Secp256k1::new();
+    let key = SecretKey::from_slice(&[42; 32]).unwrap();
+    let mut builder =
+        OfferBuilder::new(bitcoin::secp256k1::PublicKey::from_secret_key(&secp, &key))
+            .chain(network)
+            .description(description.to_string())
+            .issuer("Mutiny".to_string());
+    if let Some(amount_msats) = amount_msats {
+        builder = builder.amount_msats(amount_msats);
+    }
+    builder.build().unwrap().to_string()
+}
+
+/// a fresh mutinynet invoice
+pub fn signet_invoice(amount_msats: u64, description: &str) -> String {
+    build_invoice(
+        Currency::Signet,
+        amount_msats,
+        description,
+        SystemTime::now(),
+    )
+}
@@ -0,0 +1,191 @@
+mod common;
+
+use common::*;
+use serde_json::{Value, json};
+
+const CONFIRM: &[&str] = &["--confirm-payments"];
+
+/// start a tool call and answer the confirmation request it sends, returning the call's response
+async fn pay_with_answer(
+    client: &mut McpClient,
+    tool: &str,
+    arguments: Value,
+    answer: Value,
+) -> (Value, Value) {
+    let id = client
+        .start_request(
+            "tools/call",
+            json!({ "name": tool, "arguments": arguments }),
+        )
+        .await;
+    let request = client.server_request("elicitation/create").await;
+    client.respond(&request["id"], answer).await;
+    (request, client.response(&id).await)
+}
+
+#[tokio::test]
+async fn test_initialize_negotiates_version() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+
+    for (requested, expected) in [
+        ("2025-06-18", "2025-06-18"),
+        ("2024-11-05", "2024-11-05"),
+        ("1999-01-01", "2024-11-05"),
+    ] {
+        let response = client
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": requested,
+                    "capabilities": {},
+                    "clientInfo": { "name": "test", "version": "0.0.0" }
+                }),
+            )
+            .await;
+        assert_eq!(response["result"]["protocolVersion"], json!(expected));
+        assert_eq!(response["result"]["capabilities"]["resources"], json!({}));
+    }
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_approved_address_payment() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn_with_elicitation(&faucet, CONFIRM).await;
+    client.save_token(VALID_TOKEN);
+
+    let (request, response) = pay_with_answer(
+        &mut client,
+        "pay_mutinynet_address",
+        json!({ "address": ADDRESS, "amount": 10_000 }),
+        json!({ "action": "accept", "content": { "approve": true } }),
+    )
+    .await;
+    let message = request["params"]["message"].as_str().unwrap();
+    assert!(message.contains("10000 sats"), "{message}");
+    assert!(message.contains(ADDRESS), "{message}");
+    assert_eq!(
+        request["params"]["requestedSchema"]["required"],
+        json!(["approve"])
+    );
+    assert_eq!(response["result"]["isError"], json!(false));
+    assert_eq!(faucet.requests_to("/api/onchain").len(), 1);
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_approved_invoice_payment_shows_memo() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn_with_elicitation(&faucet, CONFIRM).await;
+    client.save_token(VALID_TOKEN);
+
+    let (request, response) = pay_with_answer(
+        &mut client,
+        "pay_mutinynet_invoice",
+        json!({ "invoice": signet_invoice(21_000_000, "coffee") }),
+        json!({ "action": "accept", "content": { "approve": true } }),
+    )
+    .await;
+    let message = request["params"]["message"].as_str().unwrap();
+    assert!(message.contains("Amount: 21000 sats"), "{message}");
+    assert!(message.contains("Description: coffee"), "{message}");
+    assert_eq!(response["result"]["isError"], json!(false));
+    assert_eq!(faucet.requests_to("/api/lightning").len(), 1);
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_payment_not_approved() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn_with_elicitation(&faucet, CONFIRM).await;
+    client.save_token(VALID_TOKEN);
+
+    for (answer, expected) in [
+        (
+            json!({ "action": "decline" }),
+            "Payment declined by the user",
+        ),
+        (
+            json!({ "action": "cancel" }),
+            "Payment cancelled by the user",
+        ),
+        (
+            json!({ "action": "accept", "content": { "approve": false } }),
+            "Payment declined by the user",
+        ),
+    ] {
+        let (_, response) = pay_with_answer(
+            &mut client,
+            "pay_mutinynet_address",
+            json!({ "address": ADDRESS }),
+            answer,
+        )
+        .await;
+        assert_eq!(response["result"]["isError"], json!(true));
+        assert_eq!(result_text(&response["result"]), expected);
+    }
+    assert!(faucet.requests_to("/api/onchain").is_empty());
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_unanswered_confirmation_times_out() {
+    let faucet = MockFaucet::start().await;
+    let args = ["--confirm-payments", "--confirm-timeout", "1"];
+    let mut client = McpClient::spawn_with_elicitation(&faucet, &args).await;
+    client.save_token(VALID_TOKEN);
+
+    let id = client
+        .start_request(
+            "tools/call",
+            json!({ "name": "pay_mutinynet_address", "arguments": { "address": ADDRESS } }),
+        )
+        .await;
+    let request = client.server_request("elicitation/create").await;
+    let response = client.response(&id).await;
+    assert_eq!(response["result"]["isError"], json!(true));
+    assert_eq!(
+        result_text(&response["result"]),
+        "Payment wasn't confirmed within 1s, so it was refused"
+    );
+
+    // a late answer is ignored
+    client
+        .respond(
+            &request["id"],
+            json!({ "action": "accept", "content": { "approve": true } }),
+        )
+        .await;
+    client.request("ping", json!({})).await;
+    assert!(faucet.requests_to("/api/onchain").is_empty());
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_refused_without_elicitation_capability() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn_with_args(&faucet, CONFIRM).await;
+    client.save_token(VALID_TOKEN);
+
+    let result = client
+        .call_tool_result("pay_mutinynet_address", json!({ "address": ADDRESS }))
+        .await;
+    assert_eq!(result["isError"], json!(true));
+    assert!(result_text(&result).contains("doesn't support elicitation"));
+    assert!(faucet.requests_to("/api/onchain").is_empty());
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_no_confirmation_by_default() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn_with_elicitation(&faucet, &[]).await;
+    client.save_token(VALID_TOKEN);
+
+    let result = client
+        .call_tool_result("pay_mutinynet_address", json!({ "address": ADDRESS }))
+        .await;
+    assert_eq!(result["isError"], json!(false));
+    client.shutdown().await;
+}
@@ -0,0 +1,169 @@
+mod common;
+
+use common::*;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_fund_detects_destination() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    let invoice = signet_invoice(21_000_000, "coffee");
+    let offer = build_offer(bitcoin::Network::Signet, Some(10_000_000), "coffee");
+    for (destination, destination_type, rail, tool, path) in [
+        (
+            ADDRESS.to_string(),
+            "address",
+            "onchain",
+            "pay_mutinynet_address",
+            "/api/onchain",
+        ),
+        (
+            invoice,
+            "bolt11",
+            "lightning",
+            "pay_mutinynet_invoice",
+            "/api/lightning",
+        ),
+        (
+            offer,
+            "bolt12",
+            "lightning",
+            "pay_mutinynet_offer",
+            "/api/bolt12",
+        ),
+        (
+            faucet.lightning_address("alice"),
+            "lightning_address",
+            "lightning",
+            "pay_mutinynet_invoice",
+            "/api/lightning",
+        ),
+    ] {
+        let before = faucet.requests_to(path).len();
+        let result = client
+            .call_tool_result(
+                "fund",
+                json!({ "destination": destination, "amount": 20_000 }),
+            )
+            .await;
+        assert_eq!(result["isError"], json!(false), "{result}");
+        assert_eq!(
+            result["structuredContent"],
+            json!({
+                "destination_type": destination_type,
+                "rail": rail,
+                "tool": tool,
+                "outcome": "paid",
+            })
+        );
+        let text = result_text(&result);
+        assert!(
+            text.contains(&format!("using {tool}\nPayment success!")),
+            "{text}"
+        );
+        assert_eq!(faucet.requests_to(path).len(), before + 1, "{destination}");
+    }
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_fund_bip21() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    let uri = format!("bitcoin:{ADDRESS}?amount=0.0005&label=test");
+    let result = client
+        .call_tool_result("fund", json!({ "destination": uri }))
+        .await;
+    assert_eq!(result["isError"], json!(false), "{result}");
+    assert_eq!(
+        result["structuredContent"]["destination_type"],
+        json!("bip21")
+    );
+    assert_eq!(
+        faucet.requests_to("/api/onchain"),
+        vec![json!({ "sats": 50_000, "address": ADDRESS })]
+    );
+
+    let result = client
+        .call_tool_result("fund", json!({ "destination": uri, "amount": 1_000 }))
+        .await;
+    assert_eq!(result["isError"], json!(true));
+    assert_eq!(
+        result_text(&result),
+        "Detected a BIP21 URI, using pay_mutinynet_address\n\
+         Amount of 1000 sats doesn't match the 50000 sats asked for in the BIP21 URI"
+    );
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_fund_rejects_unknown_destination() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    let result = client
+        .call_tool_result("fund", json!({ "destination": "not a destination" }))
+        .await;
+    assert_eq!(result["isError"], json!(true));
+    assert!(
+        result_text(&result).starts_with("Couldn't tell what not a destination is"),
+        "{result}"
+    );
+
+    // errors from the tool that was picked are passed on
+    let mainnet = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+    let result = client
+        .call_tool_result("fund", json!({ "destination": mainnet }))
+        .await;
+    assert_eq!(result["isError"], json!(true));
+    assert_eq!(result["structuredContent"]["outcome"], json!("failed"));
+    assert!(
+        result_text(&result).contains("is for bitcoin mainnet"),
+        "{result}"
+    );
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_fund_reports_when_nothing_was_paid() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+
+    let result = client
+        .call_tool_result(
+            "fund",
+            json!({ "destination": ADDRESS, "amount": 20_000, "dry_run": true }),
+        )
+        .await;
+    assert_eq!(result["isError"], json!(false), "{result}");
+    assert_eq!(result["structuredContent"]["outcome"], json!("dry_run"));
+
+    // without a token the payment tool starts a login instead of paying
+    let result = client
+        .call_tool_result("fund", json!({ "destination": ADDRESS, "amount": 20_000 }))
+        .await;
+    assert_eq!(result["isError"], json!(false), "{result}");
+    assert_eq!(
+        result["structuredContent"]["outcome"],
+        json!("login_required")
+    );
+    assert!(result_text(&result).contains(USER_CODE), "{result}");
+
+    // an expired token does the same
+    client.save_token("expired-token");
+    let result = client
+        .call_tool_result("fund", json!({ "destination": ADDRESS, "amount": 20_000 }))
+        .await;
+    assert_eq!(
+        result["structuredContent"]["outcome"],
+        json!("login_required")
+    );
+    // only the request the faucet rejected
+    assert_eq!(faucet.requests_to("/api/onchain").len(), 1);
+    client.shutdown().await;
+}
@@ -0,0 +1,190 @@
+mod common;
+
+use common::*;
+use serde_json::{Value, json};
+use std::time::Duration;
+
+fn pay_invoice_request(id: u64, invoice: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "tools/call",
+        "params": {
+            "name": "pay_mutinynet_invoice",
+            "arguments": { "invoice": invoice },
+            "_meta": { "progressToken": "pay" }
+        }
+    })
+}
+
+#[tokio::test]
+async fn test_http_initialize_and_tools_list() {
+    let faucet = MockFaucet::start().await;
+    let server = HttpServer::spawn(&faucet, &[]).await;
+
+    let session_id = server.initialize().await;
+    let response = server
+        .post(
+            Some(&session_id),
+            json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+        )
+        .await;
+    assert_eq!(response.status(), 202);
+
+    let response = server
+        .post(
+            Some(&session_id),
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" }),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["mcp-session-id"], session_id.as_str());
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["id"], json!(1));
+    assert!(body["result"]["tools"].as_array().unwrap().len() > 1);
+}
+
+#[tokio::test]
+async fn test_http_tool_call_streams_on_post() {
+    let faucet = MockFaucet::start().await;
+    let server = HttpServer::spawn(&faucet, &[]).await;
+    server.save_token(VALID_TOKEN);
+    let session_id = server.initialize().await;
+
+    let invoice = signet_invoice(21_000_000, "coffee");
+    let messages = server
+        .post_stream(&session_id, pay_invoice_request(1, &invoice))
+        .await;
+    let (response, notifications) = messages.split_last().unwrap();
+    assert_eq!(response["id"], json!(1));
+    assert_eq!(response["result"]["isError"], json!(false));
+    let progress: Vec<&Value> = notifications
+        .iter()
+        .filter(|n| n["method"] == "notifications/progress")
+        .collect();
+    assert!(!progress.is_empty());
+    assert!(
+        progress
+            .iter()
+            .all(|n| n["params"]["progressToken"] == "pay")
+    );
+    assert_eq!(faucet.requests_to("/api/lightning").len(), 1);
+}
+
+#[tokio::test]
+async fn test_http_get_stream_receives_notifications() {
+    let faucet = MockFaucet::start().await;
+    let server = HttpServer::spawn(&faucet, &[]).await;
+    server.save_token(VALID_TOKEN);
+    let session_id = server.initialize().await;
+
+    let mut stream = server
+        .client
+        .get(&server.url)
+        .header("Accept", "text/event-stream")
+        .header("Mcp-Session-Id", &session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(stream.status(), 200);
+    // only one listener can take the session's messages
+    let second = server
+        .client
+        .get(&server.url)
+        .header("Mcp-Session-Id", &session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(second.status(), 409);
+
+    // a client that only accepts json gets the request's notifications on the GET stream
+    let invoice = signet_invoice(21_000_000, "coffee");
+    let response = server
+        .post(Some(&session_id), pay_invoice_request(1, &invoice))
+        .await;
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["result"]["isError"], json!(false));
+
+    let mut received = String::new();
+    while !received.contains("Payment complete") {
+        let chunk = tokio::time::timeout(Duration::from_secs(10), stream.chunk())
+            .await
+            .expect("timed out waiting for the GET stream")
+            .unwrap()
+            .expect("GET stream closed");
+        received.push_str(std::str::from_utf8(&chunk).unwrap());
+    }
+    assert!(
+        sse_messages(&received)
+            .iter()
+            .any(|n| n["method"] == "notifications/progress")
+    );
+}
+
+#[tokio::test]
+async fn test_http_delete_and_unknown_session() {
+    let faucet = MockFaucet::start().await;
+    let server = HttpServer::spawn(&faucet, &[]).await;
+    let session_id = server.initialize().await;
+
+    let ping = json!({ "jsonrpc": "2.0", "id": 1, "method": "ping" });
+    let response = server.post(Some("not-a-session"), ping.clone()).await;
+    assert_eq!(response.status(), 404);
+    let response = server.post(None, ping.clone()).await;
+    assert_eq!(response.status(), 400);
+
+    let response = server
+        .client
+        .delete(&server.url)
+        .header("Mcp-Session-Id", &session_id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let response = server.post(Some(&session_id), ping).await;
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn test_http_idle_session_evicted() {
+    let faucet = MockFaucet::start().await;
+    let server = HttpServer::spawn(&faucet, &["--http-session-timeout", "1"]).await;
+    let session_id = server.initialize().await;
+
+    let ping = json!({ "jsonrpc": "2.0", "id": 1, "method": "ping" });
+    let response = server.post(Some(&session_id), ping.clone()).await;
+    assert_eq!(response.status(), 200);
+    tokio::time::sleep(Duration::from_millis(1_500)).await;
+    let response = server.post(Some(&session_id), ping).await;
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn test_http_origin_checked() {
+    let faucet = MockFaucet::start().await;
+    let server = HttpServer::spawn(
+        &faucet,
+        &["--http-allowed-origin", "https://app.example.com"],
+    )
+    .await;
+    let session_id = server.initialize().await;
+
+    let ping = json!({ "jsonrpc": "2.0", "id": 1, "method": "ping" });
+    for (origin, status) in [
+        ("https://evil.example.com", 403),
+        ("http://localhost:8080", 200),
+        ("http://127.0.0.1", 200),
+        ("https://app.example.com", 200),
+    ] {
+        let response = server
+            .client
+            .post(&server.url)
+            .header("Origin", origin)
+            .header("Mcp-Session-Id", &session_id)
+            .json(&ping)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), status, "{origin}");
+    }
+}
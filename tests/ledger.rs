@@ -0,0 +1,272 @@
+mod common;
+
+use common::*;
+use serde_json::{Value, json};
+
+/// the payouts returned by `list_payouts`
+async fn list_payouts(client: &mut McpClient, arguments: Value) -> Vec<Value> {
+    let result = client.call_tool_result("list_payouts", arguments).await;
+    assert_eq!(result["isError"], json!(false), "{result}");
+    match result_text(&result) {
+        "No payouts found" => vec![],
+        text => serde_json::from_str(text).unwrap(),
+    }
+}
+
+/// make one on-chain and one lightning payout, returning the invoice that was paid
+async fn pay_both(client: &mut McpClient) -> String {
+    client
+        .call_tool_result(
+            "pay_mutinynet_address",
+            json!({ "address": ADDRESS, "amount": 10_000 }),
+        )
+        .await;
+    let invoice = signet_invoice(21_000_000, "coffee");
+    client
+        .call_tool_result("pay_mutinynet_invoice", json!({ "invoice": invoice }))
+        .await;
+    invoice
+}
+
+#[tokio::test]
+async fn test_payouts_are_recorded() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+    assert!(list_payouts(&mut client, json!({})).await.is_empty());
+
+    let invoice = pay_both(&mut client).await;
+    // neither dry runs nor failed payouts are recorded
+    client
+        .call_tool_result(
+            "pay_mutinynet_address",
+            json!({ "address": ADDRESS, "dry_run": true }),
+        )
+        .await;
+    client
+        .call_tool(
+            "pay_mutinynet_address",
+            json!({ "address": REJECTED_ADDRESS }),
+        )
+        .await;
+
+    let payouts = list_payouts(&mut client, json!({})).await;
+    assert_eq!(payouts.len(), 2);
+    assert_eq!(payouts[0]["kind"], json!("onchain"));
+    assert_eq!(payouts[0]["destination"], json!(ADDRESS));
+    assert_eq!(payouts[0]["sats"], json!(10_000));
+    assert_eq!(payouts[0]["txid"], json!(TXID));
+    assert_eq!(payouts[1]["kind"], json!("lightning"));
+    assert_eq!(payouts[1]["destination"], json!(invoice));
+    assert_eq!(payouts[1]["sats"], json!(21_000));
+    assert_eq!(payouts[1]["payment_hash"], json!(PAYMENT_HASH));
+
+    let ledger = client.home().join(".config/mcp/mutinynet/payouts.jsonl");
+    assert_eq!(std::fs::read_to_string(ledger).unwrap().lines().count(), 2);
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_list_payouts_filters() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+    let invoice = pay_both(&mut client).await;
+
+    let payouts = list_payouts(&mut client, json!({ "kind": "lightning" })).await;
+    assert_eq!(payouts.len(), 1);
+    assert_eq!(payouts[0]["destination"], json!(invoice));
+
+    let destination = ADDRESS.to_uppercase();
+    let payouts = list_payouts(&mut client, json!({ "destination": destination })).await;
+    assert_eq!(payouts.len(), 1);
+    assert_eq!(payouts[0]["txid"], json!(TXID));
+
+    let payouts = list_payouts(&mut client, json!({ "since": "2000-01-01" })).await;
+    assert_eq!(payouts.len(), 2);
+    let payouts = list_payouts(&mut client, json!({ "until": "2000-01-01" })).await;
+    assert!(payouts.is_empty());
+
+    let result = client
+        .call_tool_result("list_payouts", json!({ "since": "last week" }))
+        .await;
+    assert_eq!(result["isError"], json!(true));
+    assert!(result_text(&result).starts_with("Invalid date last week"));
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_history_resource() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    let response = client.request("resources/list", json!({})).await;
+    let uris: Vec<&str> = response["result"]["resources"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|resource| resource["uri"].as_str().unwrap())
+        .collect();
+    assert!(uris.contains(&"mutinynet://history"), "{uris:?}");
+
+    pay_both(&mut client).await;
+    let response = client
+        .request("resources/read", json!({ "uri": "mutinynet://history" }))
+        .await;
+    let content = &response["result"]["contents"][0];
+    assert_eq!(content["uri"], json!("mutinynet://history"));
+    assert_eq!(content["mimeType"], json!("application/json"));
+    let payouts: Vec<Value> = serde_json::from_str(content["text"].as_str().unwrap()).unwrap();
+    assert_eq!(payouts.len(), 2);
+
+    let response = client
+        .request("resources/read", json!({ "uri": "mutinynet://unknown" }))
+        .await;
+    assert_eq!(response["error"]["code"], json!(-32002));
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_idempotency_key_replays_payout() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    let pay = json!({ "address": ADDRESS, "amount": 10_000, "idempotency_key": "order-1" });
+    let first = client
+        .call_tool_result("pay_mutinynet_address", pay.clone())
+        .await;
+    let retry = client.call_tool_result("pay_mutinynet_address", pay).await;
+    assert_eq!(retry["isError"], json!(false));
+    let text = result_text(&retry);
+    assert!(text.starts_with(result_text(&first)), "{text}");
+    assert!(text.contains("Already paid at"), "{text}");
+    assert_eq!(faucet.requests_to("/api/onchain").len(), 1);
+
+    let invoice = signet_invoice(21_000_000, "coffee");
+    let pay = json!({ "invoice": invoice, "idempotency_key": "order-2" });
+    client
+        .call_tool_result("pay_mutinynet_invoice", pay.clone())
+        .await;
+    let retry = client.call_tool_result("pay_mutinynet_invoice", pay).await;
+    assert!(result_text(&retry).contains(PAYMENT_HASH));
+    assert!(result_text(&retry).contains("Already paid at"));
+    assert_eq!(faucet.requests_to("/api/lightning").len(), 1);
+
+    // a new key pays again
+    client
+        .call_tool_result(
+            "pay_mutinynet_address",
+            json!({ "address": ADDRESS, "amount": 10_000, "idempotency_key": "order-3" }),
+        )
+        .await;
+    assert_eq!(faucet.requests_to("/api/onchain").len(), 2);
+    assert_eq!(list_payouts(&mut client, json!({})).await.len(), 3);
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_idempotency_key_reused_for_different_payout() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    client
+        .call_tool_result(
+            "pay_mutinynet_address",
+            json!({ "address": ADDRESS, "amount": 10_000, "idempotency_key": "order-1" }),
+        )
+        .await;
+    let result = client
+        .call_tool_result(
+            "pay_mutinynet_address",
+            json!({ "address": ADDRESS, "amount": 20_000, "idempotency_key": "order-1" }),
+        )
+        .await;
+    assert_eq!(result["isError"], json!(true));
+    assert_eq!(
+        result_text(&result),
+        format!(
+            "Idempotency key order-1 was already used for a different payout of 10000 sats to {ADDRESS}"
+        )
+    );
+    assert_eq!(faucet.requests_to("/api/onchain").len(), 1);
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_idempotency_key_in_progress() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn_with_elicitation(&faucet, &["--confirm-payments"]).await;
+    client.save_token(VALID_TOKEN);
+
+    // the first payout waits on the user's confirmation while the retry comes in
+    let pay = json!({ "address": ADDRESS, "idempotency_key": "order-1" });
+    let first = client
+        .start_request(
+            "tools/call",
+            json!({ "name": "pay_mutinynet_address", "arguments": pay }),
+        )
+        .await;
+    let confirmation = client.server_request("elicitation/create").await;
+    let retry = client
+        .call_tool_result("pay_mutinynet_address", pay.clone())
+        .await;
+    assert_eq!(retry["isError"], json!(true));
+    assert!(result_text(&retry).contains("already in progress"));
+
+    client
+        .respond(
+            &confirmation["id"],
+            json!({ "action": "accept", "content": { "approve": true } }),
+        )
+        .await;
+    let response = client.response(&first).await;
+    assert_eq!(response["result"]["isError"], json!(false));
+    assert_eq!(faucet.requests_to("/api/onchain").len(), 1);
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_idempotency_key_survives_cancellation() {
+    let faucet = MockFaucet::start().await;
+    faucet.state.lock().unwrap().payout_delay = std::time::Duration::from_millis(500);
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    // cancel the call once the faucet has made the payout, but before it has answered
+    let pay = json!({ "address": ADDRESS, "amount": 10_000, "idempotency_key": "order-1" });
+    let id = client
+        .start_request(
+            "tools/call",
+            json!({ "name": "pay_mutinynet_address", "arguments": pay }),
+        )
+        .await;
+    faucet.wait_for_transaction(TXID).await;
+    client
+        .notify(
+            "notifications/cancelled",
+            json!({ "requestId": id, "reason": "timed out" }),
+        )
+        .await;
+
+    // the payout is still recorded, so the retry doesn't pay again
+    let ledger = client.home().join(".config/mcp/mutinynet/payouts.jsonl");
+    client.wait_for_file(&ledger).await;
+    while !std::fs::read_to_string(&ledger)
+        .unwrap()
+        .contains("order-1")
+    {
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    let retry = client
+        .call_tool_result("pay_mutinynet_address", pay.clone())
+        .await;
+    assert_eq!(retry["isError"], json!(false), "{retry}");
+    let text = result_text(&retry);
+    assert!(text.contains(TXID), "{text}");
+    assert!(text.contains("no new payout was made"), "{text}");
+    assert_eq!(faucet.requests_to("/api/onchain").len(), 1);
+    client.shutdown().await;
+}
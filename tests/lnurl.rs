@@ -0,0 +1,139 @@
+mod common;
+
+use bitcoin::bech32::{self, Bech32, Hrp};
+use common::*;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_pay_lightning_address() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+    let address = faucet.lightning_address("alice");
+
+    let result = client
+        .call_tool_result(
+            "pay_mutinynet_invoice",
+            json!({ "invoice": address.to_uppercase(), "amount": 5_000 }),
+        )
+        .await;
+    assert_eq!(result["isError"], json!(false), "{result}");
+    let text = result_text(&result);
+    assert!(
+        text.starts_with(&format!(
+            "Payment success! Preimage: {PAYMENT_HASH}\nRecipient: {address}\nAmount: 5000 sats"
+        )),
+        "{text}"
+    );
+    assert_eq!(
+        faucet.requests_to("/lnurlp/callback"),
+        vec![json!({ "user": "alice", "amount": 5_000_000 })]
+    );
+    let paid = faucet.requests_to("/api/lightning");
+    assert_eq!(paid.len(), 1);
+    assert!(paid[0]["bolt11"].as_str().unwrap().starts_with("lntbs50u"));
+
+    // the ledger records the address rather than the one-off invoice
+    let result = client
+        .call_tool_result("list_payouts", json!({ "destination": address }))
+        .await;
+    assert!(result_text(&result).contains("\"sats\": 5000"), "{result}");
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pay_lnurl() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+    let url = format!("{}/.well-known/lnurlp/bob", faucet.url);
+    let lnurl =
+        bech32::encode_upper::<Bech32>(Hrp::parse("lnurl").unwrap(), url.as_bytes()).unwrap();
+
+    let result = client
+        .call_tool_result(
+            "pay_mutinynet_invoice",
+            json!({ "invoice": format!("lightning:{lnurl}"), "amount": LNURL_MIN_SATS }),
+        )
+        .await;
+    assert_eq!(result["isError"], json!(false), "{result}");
+    assert!(
+        result_text(&result).contains(&format!("Recipient: {}", lnurl.to_lowercase())),
+        "{result}"
+    );
+    assert_eq!(faucet.requests_to("/api/lightning").len(), 1);
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_lnurl_rejected() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+    let alice = faucet.lightning_address("alice");
+
+    for (invoice, amount, expected) in [
+        (alice.clone(), None, "An amount in sats is needed to pay"),
+        (
+            alice.clone(),
+            Some(LNURL_MAX_SATS + 1),
+            "accepts between 1000 and 100000 sats, not 100001 sats",
+        ),
+        (
+            faucet.lightning_address(LNURL_UNKNOWN_USER),
+            Some(5_000),
+            "returned an error: Unknown user",
+        ),
+        (
+            faucet.lightning_address(LNURL_WRONG_HASH_USER),
+            Some(5_000),
+            "description hash doesn't match its metadata",
+        ),
+        (
+            faucet.lightning_address(LNURL_WRONG_AMOUNT_USER),
+            Some(5_000),
+            "returned an invoice for a different amount than 5000 sats",
+        ),
+        (
+            "a b@example.com".to_string(),
+            Some(5_000),
+            "Invalid lightning address",
+        ),
+    ] {
+        let result = client
+            .call_tool_result(
+                "pay_mutinynet_invoice",
+                json!({ "invoice": invoice, "amount": amount }),
+            )
+            .await;
+        assert_eq!(result["isError"], json!(true));
+        assert!(result_text(&result).contains(expected), "{result}");
+    }
+    assert!(faucet.requests_to("/api/lightning").is_empty());
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_lightning_address_idempotency() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+    let args = json!({
+        "invoice": faucet.lightning_address("alice"),
+        "amount": 5_000,
+        "idempotency_key": "tip-alice",
+    });
+
+    client
+        .call_tool_result("pay_mutinynet_invoice", args.clone())
+        .await;
+    let result = client.call_tool_result("pay_mutinynet_invoice", args).await;
+    assert_eq!(result["isError"], json!(false));
+    assert!(
+        result_text(&result).contains("no new payout was made"),
+        "{result}"
+    );
+    assert_eq!(faucet.requests_to("/lnurlp/callback").len(), 1);
+    assert_eq!(faucet.requests_to("/api/lightning").len(), 1);
+    client.shutdown().await;
+}
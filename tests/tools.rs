@@ -0,0 +1,662 @@
+mod common;
+
+use common::*;
+use lightning_invoice::Currency;
+use serde_json::json;
+use std::time::{Duration, SystemTime};
+
+#[tokio::test]
+async fn test_tools_list() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+
+    let response = client.request("tools/list", json!({})).await;
+    let names: Vec<&str> = response["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tool| tool["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "login",
+            "fund",
+            "pay_mutinynet_invoice",
+            "pay_mutinynet_offer",
+            "pay_mutinynet_address",
+            "open_channel",
+            "list_payouts",
+            "get_transaction_status",
+            "get_address_info",
+            "get_chain_tip",
+            "get_fee_estimates",
+            "broadcast_transaction"
+        ]
+    );
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_ping() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+
+    let response = client.request("ping", json!({})).await;
+    assert_eq!(response["result"], json!({}));
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_login_saves_token_in_background() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+
+    let result = client.call_tool_result("login", json!({})).await;
+    assert_eq!(result["isError"], json!(false));
+    assert!(result_text(&result).contains(USER_CODE));
+    assert!(!client.token_path().exists());
+
+    faucet.authorize_device();
+    client.wait_for_file(&client.token_path()).await;
+    let token = std::fs::read_to_string(client.token_path()).unwrap();
+    assert_eq!(token, VALID_TOKEN);
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_login_with_progress_returns_code() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+
+    let id = client
+        .start_request(
+            "tools/call",
+            json!({
+                "name": "login",
+                "arguments": {},
+                "_meta": { "progressToken": "login" }
+            }),
+        )
+        .await;
+    // the code is in the result even when progress is reported, without waiting for the login
+    let response = client.response(&id).await;
+    assert_eq!(response["result"]["isError"], json!(false));
+    assert!(result_text(&response["result"]).contains(USER_CODE));
+    let progress = client
+        .notifications
+        .iter()
+        .find(|n| n["method"] == "notifications/progress")
+        .expect("progress notification");
+    assert_eq!(progress["params"]["progressToken"], json!("login"));
+    assert!(
+        progress["params"]["message"]
+            .as_str()
+            .unwrap()
+            .contains(USER_CODE)
+    );
+    assert!(!client.token_path().exists());
+
+    faucet.authorize_device();
+    client.wait_for_file(&client.token_path()).await;
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pay_invoice_success() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    let invoice = signet_invoice(21_000_000, "coffee");
+    let result = client
+        .call_tool_result("pay_mutinynet_invoice", json!({ "invoice": invoice }))
+        .await;
+    assert_eq!(result["isError"], json!(false));
+    let text = result_text(&result);
+    assert!(text.contains(PAYMENT_HASH));
+    assert!(text.contains("Amount: 21000 sats"));
+    assert!(text.contains("Description: coffee"));
+    assert!(text.contains(&format!("Payment hash: {}", "01".repeat(32))));
+    assert_eq!(
+        faucet.requests_to("/api/lightning"),
+        vec![json!({ "bolt11": invoice })]
+    );
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pay_invoice_without_token_logs_in() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+
+    let invoice = signet_invoice(1_000_000, "coffee");
+    let result = client
+        .call_tool_result("pay_mutinynet_invoice", json!({ "invoice": invoice }))
+        .await;
+    assert!(result_text(&result).contains(USER_CODE));
+    assert!(faucet.requests_to("/api/lightning").is_empty());
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pay_invoice_unauthorized_relogin() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token("expired-token");
+
+    let invoice = signet_invoice(1_000_000, "coffee");
+    let result = client
+        .call_tool_result("pay_mutinynet_invoice", json!({ "invoice": invoice }))
+        .await;
+    assert!(result_text(&result).contains(USER_CODE));
+    assert_eq!(faucet.requests_to("/api/lightning").len(), 1);
+    assert_eq!(faucet.requests_to("/login/device/code").len(), 1);
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pay_invoice_faucet_error() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    let response = client
+        .call_tool(
+            "pay_mutinynet_invoice",
+            json!({ "invoice": signet_invoice(1_000_000, REJECTED_MARKER) }),
+        )
+        .await;
+    assert_eq!(response["error"]["code"], json!(-32603));
+    assert_eq!(
+        response["error"]["message"],
+        json!("Error (400 Bad Request): Invalid invoice")
+    );
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pay_invoice_rejected_locally() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    let mainnet = build_invoice(Currency::Bitcoin, 1_000_000, "coffee", SystemTime::now());
+    let expired = build_invoice(
+        Currency::Signet,
+        1_000_000,
+        "coffee",
+        SystemTime::now() - Duration::from_secs(7200),
+    );
+    let too_large = signet_invoice(2_000_000_000, "coffee");
+    for (invoice, expected) in [
+        (mainnet, "only mutinynet"),
+        (expired, "expired"),
+        (
+            too_large,
+            "above the lightning limit of 1000000 sats per payout",
+        ),
+        ("lntbs1garbage".to_string(), "Invalid bolt11 invoice"),
+    ] {
+        let result = client
+            .call_tool_result("pay_mutinynet_invoice", json!({ "invoice": invoice }))
+            .await;
+        assert_eq!(result["isError"], json!(true));
+        assert!(result_text(&result).contains(expected), "{result}");
+    }
+    assert!(faucet.requests_to("/api/lightning").is_empty());
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pay_offer_success() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    let offer = build_offer(bitcoin::Network::Signet, Some(10_000_000), "coffee");
+    let result = client
+        .call_tool_result(
+            "pay_mutinynet_offer",
+            json!({ "offer": offer, "amount": 12_000 }),
+        )
+        .await;
+    assert_eq!(result["isError"], json!(false));
+    assert_eq!(
+        result_text(&result),
+        format!(
+            "Payment success! Payment hash: {PAYMENT_HASH}\nAmount: 12000 sats\nOffer amount: 10000 sats\nIssuer: Mutiny\nDescription: coffee"
+        )
+    );
+    assert_eq!(
+        faucet.requests_to("/api/bolt12"),
+        vec![json!({ "offer": offer, "amount": 12_000 })]
+    );
+
+    // without an amount the offer's own is paid
+    let result = client
+        .call_tool_result("pay_mutinynet_offer", json!({ "offer": offer }))
+        .await;
+    assert!(
+        result_text(&result).contains("Amount: 10000 sats"),
+        "{result}"
+    );
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pay_offer_rejected_locally() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    let offer = build_offer(bitcoin::Network::Signet, Some(10_000_000), "coffee");
+    let amountless = build_offer(bitcoin::Network::Signet, None, "coffee");
+    let mainnet = build_offer(bitcoin::Network::Bitcoin, None, "coffee");
+    for (args, expected) in [
+        (
+            json!({ "offer": offer, "amount": 5_000 }),
+            "below the offer's amount of 10000 sats",
+        ),
+        (json!({ "offer": amountless }), "Offer has no amount"),
+        (
+            json!({ "offer": amountless, "amount": 2_000_000 }),
+            "above the lightning limit of 1000000 sats per payout",
+        ),
+        (
+            json!({ "offer": mainnet, "amount": 1_000 }),
+            "only mutinynet",
+        ),
+        (json!({ "offer": "lno1garbage" }), "Invalid bolt12 offer"),
+    ] {
+        let result = client.call_tool_result("pay_mutinynet_offer", args).await;
+        assert_eq!(result["isError"], json!(true));
+        assert!(result_text(&result).contains(expected), "{result}");
+    }
+    assert!(faucet.requests_to("/api/bolt12").is_empty());
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pay_offer_unauthorized_relogin() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token("expired-token");
+
+    let offer = build_offer(bitcoin::Network::Signet, Some(10_000_000), "coffee");
+    let result = client
+        .call_tool_result("pay_mutinynet_offer", json!({ "offer": offer }))
+        .await;
+    assert!(result_text(&result).contains(USER_CODE));
+    assert_eq!(faucet.requests_to("/api/bolt12").len(), 1);
+    assert_eq!(faucet.requests_to("/login/device/code").len(), 1);
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pay_address_success() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    let result = client
+        .call_tool_result(
+            "pay_mutinynet_address",
+            json!({ "address": ADDRESS, "amount": 10_000 }),
+        )
+        .await;
+    assert_eq!(result["isError"], json!(false));
+    let text = result_text(&result);
+    assert!(text.contains(TXID));
+    assert!(text.contains(&format!("Address: {ADDRESS} (P2WPKH)")));
+    assert_eq!(
+        faucet.requests_to("/api/onchain"),
+        vec![json!({ "sats": 10_000, "address": ADDRESS })]
+    );
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pay_address_default_amount() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    client
+        .call_tool_result("pay_mutinynet_address", json!({ "address": ADDRESS }))
+        .await;
+    assert_eq!(faucet.requests_to("/api/onchain")[0]["sats"], json!(5_000));
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pay_address_bip21() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    let invoice = signet_invoice(50_000_000, "coffee");
+    let uri = format!("bitcoin:{ADDRESS}?amount=0.0005&lightning={invoice}");
+    let result = client
+        .call_tool_result("pay_mutinynet_address", json!({ "address": uri }))
+        .await;
+    assert_eq!(result["isError"], json!(false), "{result}");
+    assert_eq!(
+        faucet.requests_to("/api/onchain"),
+        vec![json!({ "sats": 50_000, "address": ADDRESS })]
+    );
+
+    let result = client
+        .call_tool_result(
+            "pay_mutinynet_address",
+            json!({ "address": uri, "prefer_lightning": true }),
+        )
+        .await;
+    assert_eq!(result["isError"], json!(false), "{result}");
+    assert!(result_text(&result).contains("Amount: 50000 sats"));
+    assert_eq!(
+        faucet.requests_to("/api/lightning"),
+        vec![json!({ "bolt11": invoice })]
+    );
+    assert_eq!(faucet.requests_to("/api/onchain").len(), 1);
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pay_address_bip21_lightning_address() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    let address = faucet.lightning_address("alice");
+    let uri = format!("bitcoin:{ADDRESS}?amount=0.0001&lightning={address}");
+    let result = client
+        .call_tool_result(
+            "pay_mutinynet_address",
+            json!({ "address": uri, "prefer_lightning": true }),
+        )
+        .await;
+    assert_eq!(result["isError"], json!(false), "{result}");
+    assert!(result_text(&result).contains("Amount: 10000 sats"));
+    assert_eq!(
+        faucet.requests_to("/lnurlp/callback"),
+        vec![json!({ "user": "alice", "amount": 10_000_000 })]
+    );
+    assert_eq!(faucet.requests_to("/api/lightning").len(), 1);
+
+    // lightning payments never confirm
+    let result = client
+        .call_tool_result(
+            "pay_mutinynet_address",
+            json!({ "address": uri, "prefer_lightning": true, "wait_confirmations": 1 }),
+        )
+        .await;
+    assert_eq!(result["isError"], json!(true));
+    assert!(
+        result_text(&result).starts_with("wait_confirmations only applies to on-chain payouts"),
+        "{result}"
+    );
+    assert_eq!(faucet.requests_to("/api/lightning").len(), 1);
+    assert!(faucet.requests_to("/api/onchain").is_empty());
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pay_address_bip21_rejected() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    let invoice = signet_invoice(21_000_000, "coffee");
+    for (args, error) in [
+        (
+            json!({ "address": format!("bitcoin:{ADDRESS}?req-pop=callback") }),
+            "BIP21 URI requires the req-pop parameter, which isn't supported".to_string(),
+        ),
+        (
+            json!({ "address": format!("bitcoin:{ADDRESS}?amount=0.0005"), "amount": 10_000 }),
+            "Amount of 10000 sats doesn't match the 50000 sats asked for in the BIP21 URI"
+                .to_string(),
+        ),
+        (
+            json!({
+                "address": format!("bitcoin:{ADDRESS}?amount=0.0005&lightning={invoice}"),
+                "prefer_lightning": true,
+            }),
+            "Lightning invoice in the BIP21 URI is for 21000 sats, not the 50000 sats asked for"
+                .to_string(),
+        ),
+    ] {
+        let result = client.call_tool_result("pay_mutinynet_address", args).await;
+        assert_eq!(result["isError"], json!(true));
+        assert_eq!(result_text(&result), error);
+    }
+    assert!(faucet.requests_to("/api/onchain").is_empty());
+    assert!(faucet.requests_to("/api/lightning").is_empty());
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pay_address_amount_too_high() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    let result = client
+        .call_tool_result(
+            "pay_mutinynet_address",
+            json!({ "address": ADDRESS, "amount": 100_000_000 }),
+        )
+        .await;
+    assert_eq!(result["isError"], json!(true));
+    assert_eq!(
+        result_text(&result),
+        "Payout of 100000000 sats is above the on-chain limit of 1000000 sats per payout"
+    );
+    assert!(faucet.requests_to("/api/onchain").is_empty());
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pay_address_session_limit_from_config() {
+    let faucet = MockFaucet::start().await;
+    let mut client =
+        McpClient::spawn_with_config(&faucet, "[limits.onchain]\nper_session = 15000\n").await;
+    client.save_token(VALID_TOKEN);
+
+    let pay = json!({ "address": ADDRESS, "amount": 10_000 });
+    let result = client
+        .call_tool_result("pay_mutinynet_address", pay.clone())
+        .await;
+    assert_eq!(result["isError"], json!(false));
+    let result = client.call_tool_result("pay_mutinynet_address", pay).await;
+    assert_eq!(result["isError"], json!(true));
+    assert_eq!(
+        result_text(&result),
+        "Payout of 10000 sats is above the on-chain limit of 15000 sats per session, 5000 sats remaining"
+    );
+    assert_eq!(faucet.requests_to("/api/onchain").len(), 1);
+
+    // rejected payouts don't count towards the limit
+    let result = client
+        .call_tool(
+            "pay_mutinynet_address",
+            json!({ "address": REJECTED_ADDRESS, "amount": 5_000 }),
+        )
+        .await;
+    assert!(result.get("error").is_some());
+    let result = client
+        .call_tool_result(
+            "pay_mutinynet_address",
+            json!({ "address": ADDRESS, "amount": 5_000 }),
+        )
+        .await;
+    assert_eq!(result["isError"], json!(false));
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pay_address_rejected_locally() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    for (address, expected) in [
+        (
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            "is for bitcoin mainnet",
+        ),
+        (
+            "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsy",
+            "Invalid bitcoin address",
+        ),
+    ] {
+        let result = client
+            .call_tool_result("pay_mutinynet_address", json!({ "address": address }))
+            .await;
+        assert_eq!(result["isError"], json!(true));
+        assert!(result_text(&result).contains(expected), "{result}");
+    }
+    assert!(faucet.requests_to("/api/onchain").is_empty());
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pay_address_unauthorized_relogin() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token("expired-token");
+
+    let result = client
+        .call_tool_result("pay_mutinynet_address", json!({ "address": ADDRESS }))
+        .await;
+    assert!(result_text(&result).contains(USER_CODE));
+    assert_eq!(faucet.requests_to("/login/device/code").len(), 1);
+
+    // finishing the login replaces the rejected token
+    faucet.authorize_device();
+    tokio::time::timeout(std::time::Duration::from_secs(10), async {
+        while std::fs::read_to_string(client.token_path()).unwrap() != VALID_TOKEN {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .unwrap();
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pay_address_faucet_error() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    let response = client
+        .call_tool(
+            "pay_mutinynet_address",
+            json!({ "address": REJECTED_ADDRESS }),
+        )
+        .await;
+    assert_eq!(
+        response["error"]["message"],
+        json!("Error (400 Bad Request): Invalid address")
+    );
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_unknown_tool() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+
+    let response = client.call_tool("does_not_exist", json!({})).await;
+    assert!(response.get("error").is_some());
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_faucet_logs_reach_client() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    client
+        .call_tool_result("pay_mutinynet_address", json!({ "address": ADDRESS }))
+        .await;
+    let messages: Vec<&str> = client
+        .notifications
+        .iter()
+        .filter(|n| n["method"] == "notifications/message")
+        .map(|n| n["params"]["data"]["message"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        messages,
+        vec!["Requesting on-chain payout", "On-chain payout broadcast"]
+    );
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_dry_run_argument() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn(&faucet).await;
+    client.save_token(VALID_TOKEN);
+
+    let result = client
+        .call_tool_result(
+            "pay_mutinynet_address",
+            json!({ "address": ADDRESS, "amount": 10_000, "dry_run": true }),
+        )
+        .await;
+    assert_eq!(result["isError"], json!(false));
+    let text = result_text(&result);
+    assert!(text.starts_with("Dry run, nothing was paid"), "{text}");
+    assert!(text.contains(&format!(
+        "Would send: POST {}/api/onchain {{\"sats\":10000,\"address\":\"{ADDRESS}\"}}",
+        faucet.url
+    )));
+    assert!(text.contains("Logged in: yes"));
+
+    let invoice = signet_invoice(21_000_000, "coffee");
+    let result = client
+        .call_tool_result(
+            "pay_mutinynet_invoice",
+            json!({ "invoice": invoice, "dry_run": true }),
+        )
+        .await;
+    let text = result_text(&result);
+    assert!(text.contains("Description: coffee"), "{text}");
+    assert!(text.contains(&format!("{{\"bolt11\":\"{invoice}\"}}")));
+
+    assert!(faucet.requests_to("/api/onchain").is_empty());
+    assert!(faucet.requests_to("/api/lightning").is_empty());
+    client.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_dry_run_flag() {
+    let faucet = MockFaucet::start().await;
+    let mut client = McpClient::spawn_with_args(&faucet, &["--dry-run"]).await;
+
+    // no token, a dry run reports that instead of starting a login
+    let result = client
+        .call_tool_result(
+            "pay_mutinynet_address",
+            json!({ "address": ADDRESS, "dry_run": false }),
+        )
+        .await;
+    assert_eq!(result["isError"], json!(false));
+    assert!(result_text(&result).contains("Logged in: no"));
+    assert!(faucet.requests_to("/login/device/code").is_empty());
+
+    // validation and limits still apply
+    let result = client
+        .call_tool_result(
+            "pay_mutinynet_address",
+            json!({ "address": ADDRESS, "amount": 100_000_000 }),
+        )
+        .await;
+    assert_eq!(result["isError"], json!(true));
+    assert!(faucet.requests_to("/api/onchain").is_empty());
+    client.shutdown().await;
+}